@@ -1,119 +1,370 @@
-use reqwest::{self, Client};
+use super::auth::Authenticator;
+use super::cassette::{Cassette, CassetteMode};
+use super::error::ApiError;
+use super::inspector::API_CALL_SPAN;
+use super::limiter::RequestLimiter;
+use super::retry::RetryPolicy;
+use crate::config::{AppConfig, HttpConfig};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT};
+use reqwest::{Certificate, Client, NoProxy, Proxy, RequestBuilder, StatusCode, Url};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::field::{display, Empty};
+use tracing::{Instrument, Span};
 
-const URL: &str = "https://backend-rust-277582128315.us-central1.run.app/";
+pub use super::page::{Page, PageRequest};
+pub use super::trip::Trip;
 
+/// Query for `GET /trip/price`: trips whose total is between `min` and `max`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Trip {
-    pub vendor_id: String,
-    pub tpep_pickup_datetime: String,
-    pub tpep_dropoff_datetime: String,
-    pub passenger_count: String,
-    pub trip_distance: String,
-    pub ratecode_id: String,
-    pub store_and_fwd_flag: String,
-    pub pu_location_id: String,
-    pub do_location_id: String,
-    pub payment_type: String,
-    pub fare_amount: String,
-    pub extra: String,
-    pub mta_tax: String,
-    pub tip_amount: String,
-    pub tolls_amount: String,
-    pub improvement_surcharge: String,
-    pub total_amount: String,
-    pub congestion_surcharge: String,
-    pub index: String,
+pub struct GetByPriceRangeInput {
+    pub min: String,
+    pub max: String,
+    #[serde(flatten)]
+    pub paging: PageRequest,
+}
+
+/// Query for `GET /trip/destination/{destination}`; only the paging goes in
+/// the query string.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetByDestinationInput {
+    pub destination: String,
+    #[serde(flatten)]
+    pub paging: PageRequest,
 }
 
-pub async fn is_alive() -> Result<bool, Box<dyn Error>> {
-    reqwest::get(URL).await?.text().await?;
-    println!("Server is alive");
-    Ok(true)
+/// Client for the trip backend. Holds a single `reqwest::Client`, so every
+/// call, from any clone, reuses the same connection pool.
+///
+/// All calls are GETs, so transient failures are retried following `retry`.
+/// Each call runs in an `api_call` tracing span; see [`super::inspector`].
+/// Credentials come from `auth`; a rejected bearer token is refreshed once
+/// and the request repeated. With a [`Cassette`], responses are recorded to
+/// or replayed from a fixture file.
+///
+/// At most `max_concurrent_requests` requests are on the wire at once; the
+/// rest queue in `limiter`. A retry queues again, so
+/// the backoff doesn't hold a slot.
+#[derive(Debug, Clone)]
+pub struct TripApiClient {
+    http: Client,
+    base_url: Url,
+    retry: RetryPolicy,
+    auth: Authenticator,
+    cassette: Option<Arc<Cassette>>,
+    limiter: RequestLimiter,
 }
 
-pub async fn get_by_index(index: String) -> Result<Trip, Box<dyn Error>> {
-    let url: String = URL.to_owned() + "/trip/" + &index;
-    let response = reqwest::get(url).await?;
-    if response.status().is_success() {
-        let trip: Trip = response.json().await?;
-        Ok(trip)
-    } else {
-        Err(format!("Error: Status {}", response.status()).into())
+impl TripApiClient {
+    pub fn new(
+        base_url: Url,
+        config: &HttpConfig,
+        retry: RetryPolicy,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+        for (name, value) in &config.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| format!("Invalid header name '{}': {}", name, e))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| format!("Invalid value for header '{}': {}", name, e))?;
+            headers.insert(name, value);
+        }
+
+        let mut builder = Client::builder()
+            .connect_timeout(config.connect_timeout)
+            .read_timeout(config.read_timeout)
+            .user_agent(config.user_agent.as_str())
+            .default_headers(headers);
+        if let Some(proxy) = &config.proxy {
+            let no_proxy = NoProxy::from_string(&config.no_proxy.join(","));
+            builder = builder.proxy(Proxy::all(proxy.clone())?.no_proxy(no_proxy));
+        }
+        for path in &config.ca_certs {
+            for cert in read_certificates(path)? {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        let http = builder.build()?;
+
+        Ok(TripApiClient {
+            http,
+            base_url,
+            retry,
+            auth: Authenticator::default(),
+            cassette: None,
+            limiter: RequestLimiter::new(config.max_concurrent_requests),
+        })
+    }
+
+    pub fn from_config(config: &AppConfig) -> Result<Self, Box<dyn Error>> {
+        let mut client = Self::new(config.api_url.clone(), &config.http, config.retry.clone())?
+            .with_auth(Authenticator::new(&config.auth)?);
+        if let Some(cassette) = &config.cassette {
+            client = client.with_cassette(Arc::new(Cassette::open(cassette)?));
+        }
+        Ok(client)
+    }
+
+    pub fn with_auth(mut self, auth: Authenticator) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Records every response to `cassette`, or answers from it without
+    /// touching the network, depending on its mode.
+    pub fn with_cassette(mut self, cassette: Arc<Cassette>) -> Self {
+        self.cassette = Some(cassette);
+        self
+    }
+
+    pub fn cassette(&self) -> Option<&Arc<Cassette>> {
+        self.cassette.as_ref()
+    }
+
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// Appends path segments to the base URL, percent-encoding each one.
+    fn endpoint(&self, segments: &[&str]) -> Url {
+        let mut url = self.base_url.clone();
+        if let Ok(mut path) = url.path_segments_mut() {
+            path.pop_if_empty().extend(segments);
+        }
+        url
+    }
+
+    pub async fn is_alive(&self) -> Result<bool, ApiError> {
+        let span = call_span(&self.base_url);
+        let started = Instant::now();
+        let result = async {
+            let (request, _) = self.authorize(self.http.get(self.base_url.clone())).await?;
+            let response = self.send(request).await?;
+            if !response.status.is_success() {
+                return Err(response.into_error());
+            }
+            Span::current().record("bytes", response.body.len() as u64);
+            Ok(true)
+        }
+        .instrument(span.clone())
+        .await;
+        finish_span(&span, started, 1, &result);
+        result
+    }
+
+    /// Fetches a new bearer token through the refresh hook. Returns `false`
+    /// when there is no hook, so only a restart with new credentials helps.
+    pub async fn reauthenticate(&self) -> Result<bool, ApiError> {
+        self.auth.reauthenticate().await
+    }
+
+    pub async fn get_by_index(&self, index: &str) -> Result<Trip, ApiError> {
+        let url = self.endpoint(&["trip", index]);
+        self.get_json(|| self.http.get(url.clone())).await
+    }
+
+    pub async fn get_by_price_range(
+        &self,
+        data: &GetByPriceRangeInput,
+    ) -> Result<Page<Trip>, ApiError> {
+        let url = self.endpoint(&["trip", "price"]);
+        self.get_json(|| self.http.get(url.clone()).query(data))
+            .await
+    }
+
+    pub async fn get_by_destination(
+        &self,
+        data: &GetByDestinationInput,
+    ) -> Result<Page<Trip>, ApiError> {
+        let url = self.endpoint(&["trip", "destination", &data.destination]);
+        self.get_json(|| self.http.get(url.clone()).query(&data.paging))
+            .await
+    }
+
+    /// Builds and sends a fresh request per attempt, retrying transient errors.
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        request: impl Fn() -> RequestBuilder,
+    ) -> Result<T, ApiError> {
+        let span = match request().build() {
+            Ok(built) => call_span(built.url()),
+            Err(e) => return Err(e.into()),
+        };
+        let started = Instant::now();
+        let attempts = AtomicU64::new(0);
+        let result = self
+            .retry
+            .run(|| {
+                attempts.fetch_add(1, Ordering::Relaxed);
+                self.fetch_authorized(&request)
+            })
+            .instrument(span.clone())
+            .await;
+        finish_span(&span, started, attempts.into_inner(), &result);
+        result
+    }
+
+    /// One attempt with the current credentials, repeated once with a fresh
+    /// token if the backend rejects them and a refresh hook is set.
+    async fn fetch_authorized<T: DeserializeOwned>(
+        &self,
+        request: &impl Fn() -> RequestBuilder,
+    ) -> Result<T, ApiError> {
+        let (authorized, generation) = self.authorize(request()).await?;
+        match self.send(authorized).await?.into_json() {
+            Err(ApiError::Unauthorized(_)) if self.auth.can_refresh() && !self.replaying() => {
+                self.auth.refresh(generation).await?;
+                let (authorized, _) = self.authorize(request()).await?;
+                self.send(authorized).await?.into_json()
+            }
+            result => result,
+        }
+    }
+
+    fn replaying(&self) -> bool {
+        self.cassette
+            .as_ref()
+            .is_some_and(|cassette| cassette.mode() == CassetteMode::Replay)
+    }
+
+    /// Adds the credentials, except when replaying: cassettes hold no
+    /// credentials, and a token command shouldn't run for an offline run.
+    async fn authorize(&self, request: RequestBuilder) -> Result<(RequestBuilder, u64), ApiError> {
+        if self.replaying() {
+            return Ok((request, 0));
+        }
+        self.auth.authorize(request).await
+    }
+
+    /// Sends the request, or answers it from the cassette when replaying, and
+    /// records the status on the current `api_call` span. When recording,
+    /// the response is saved to the cassette too.
+    async fn send(&self, request: RequestBuilder) -> Result<RawResponse, ApiError> {
+        let request = request.build()?;
+        let method = request.method().to_string();
+        let url = request.url().clone();
+        let (status, body) = match &self.cassette {
+            Some(cassette) if cassette.mode() == CassetteMode::Replay => {
+                let (status, body) = cassette.play(&method, &url)?;
+                let status = StatusCode::from_u16(status).map_err(|_| {
+                    ApiError::Cassette(format!(
+                        "invalid status {} recorded for {}",
+                        status,
+                        url.path()
+                    ))
+                })?;
+                (status, body)
+            }
+            cassette => {
+                let queued = Instant::now();
+                let _permit = self.limiter.acquire().await;
+                Span::current().record("queued_ms", queued.elapsed().as_millis() as u64);
+                let response = self.http.execute(request).await?;
+                let status = response.status();
+                let body = response.bytes().await?.to_vec();
+                if let Some(cassette) = cassette {
+                    cassette.store(&method, &url, status.as_u16(), &body)?;
+                }
+                (status, body)
+            }
+        };
+        Span::current().record("status", status.as_u16());
+        Ok(RawResponse {
+            status,
+            path: url.path().to_string(),
+            body,
+        })
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct GetByPriceRangeInput {
-    pub min: String,
-    pub max: String,
-    pub page: String, // Keep as 'pages' (plural) for price range endpoint
-    pub per_page: String,
+/// Every certificate of a PEM file, which may hold a whole chain.
+fn read_certificates(path: &Path) -> Result<Vec<Certificate>, Box<dyn Error>> {
+    let pem = std::fs::read(path)
+        .map_err(|e| format!("Cannot read CA certificate {}: {}", path.display(), e))?;
+    let certs = Certificate::from_pem_bundle(&pem)
+        .map_err(|e| format!("Invalid CA certificate {}: {}", path.display(), e))?;
+    if certs.is_empty() {
+        return Err(format!("No certificate found in {}", path.display()).into());
+    }
+    Ok(certs)
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct GetByPriceRangeOutput {
-    pub items: Vec<Trip>,
-    pub page: u32,
-    pub pages: u32,
-    pub per_page: u32,
-    pub time_ms: u32,
-    pub total: u32,
+/// Opens the `api_call` span of a request. Only the path and query string
+/// are recorded; headers, and so credentials, never reach the span.
+fn call_span(url: &Url) -> Span {
+    tracing::info_span!(
+        API_CALL_SPAN,
+        endpoint = url.path(),
+        params = url.query().unwrap_or_default(),
+        status = Empty,
+        bytes = Empty,
+        client_ms = Empty,
+        server_ms = Empty,
+        queued_ms = Empty,
+        attempts = Empty,
+        error = Empty,
+    )
 }
 
-pub async fn get_by_price_range(
-    data: &GetByPriceRangeInput,
-) -> Result<GetByPriceRangeOutput, Box<dyn Error>> {
-    let client = Client::new();
-    let url: String = URL.to_owned() + "/trip/price";
-    let response = client.get(url).query(data).send().await?;
-
-    if response.status().is_success() {
-        let output: GetByPriceRangeOutput = response.json().await?;
-        Ok(output)
-    } else {
-        let status = response.status();
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Error desconocido".to_string());
-        Err(format!("Error {}: {}", status, error_text).into())
+fn finish_span<T>(span: &Span, started: Instant, attempts: u64, result: &Result<T, ApiError>) {
+    span.record("client_ms", started.elapsed().as_millis() as u64);
+    span.record("attempts", attempts);
+    if let Err(e) = result {
+        span.record("error", display(e));
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct GetByDestinationInput {
-    pub destination: String,
-    pub page: String, // Use 'page' (singular) for destination endpoint
-    pub per_page: String,
+/// `time_ms` as reported in list responses.
+#[derive(Deserialize)]
+struct ServerTiming {
+    time_ms: Option<u64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct GetByDestinationQuery {
-    pub page: String, // Use 'page' (singular) for destination endpoint
-    pub per_page: String,
+/// A response as received from the backend or replayed from a cassette.
+struct RawResponse {
+    status: StatusCode,
+    path: String,
+    body: Vec<u8>,
 }
 
-pub async fn get_by_destination(
-    data: &GetByDestinationInput,
-) -> Result<GetByPriceRangeOutput, Box<dyn Error>> {
-    let client = Client::new();
-    let query: GetByDestinationQuery = GetByDestinationQuery {
-        page: data.page.clone(),
-        per_page: data.per_page.clone(),
-    };
-    let url: String = URL.to_owned() + "/trip/destination/" + &data.destination;
-    let response = client.get(url).query(&query).send().await?;
-    if response.status().is_success() {
-        let output: GetByPriceRangeOutput = response.json().await?;
-        Ok(output)
-    } else {
-        let status = response.status();
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Error desconocido".to_string());
-        Err(format!("Error {}: {}", status, error_text).into())
+impl RawResponse {
+    /// Decodes a successful JSON body into `T`, recording the response on
+    /// the current `api_call` span.
+    fn into_json<T: DeserializeOwned>(self) -> Result<T, ApiError> {
+        if !self.status.is_success() {
+            return Err(self.into_error());
+        }
+        let span = Span::current();
+        span.record("bytes", self.body.len() as u64);
+        if let Ok(ServerTiming {
+            time_ms: Some(time_ms),
+        }) = serde_json::from_slice(&self.body)
+        {
+            span.record("server_ms", time_ms);
+        }
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+
+    /// Maps a non-success response to [`ApiError::NotFound`],
+    /// [`ApiError::Unauthorized`] or [`ApiError::Http`].
+    fn into_error(self) -> ApiError {
+        let status = self.status;
+        if matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) {
+            return ApiError::Unauthorized(format!("HTTP {} on {}", status.as_u16(), self.path));
+        }
+        if status == StatusCode::NOT_FOUND {
+            ApiError::NotFound(self.path)
+        } else {
+            ApiError::Http {
+                status: status.as_u16(),
+                body: String::from_utf8_lossy(&self.body).into_owned(),
+            }
+        }
     }
 }
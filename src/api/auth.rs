@@ -0,0 +1,354 @@
+use super::error::ApiError;
+use super::sync::{read, write};
+use async_trait::async_trait;
+use reqwest::header::{HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::RequestBuilder;
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+use std::process::Stdio;
+use std::sync::{Arc, RwLock};
+use tokio::sync::Mutex;
+
+/// Header that carries the API key when none is configured.
+pub const DEFAULT_API_KEY_HEADER: &str = "X-API-Key";
+
+/// A credential. `Debug` and `Display` never print the value, so configs and
+/// errors holding one are safe to log.
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Secret(value.into())
+    }
+
+    /// The actual value, for the request header and nothing else.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(***)")
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("***")
+    }
+}
+
+/// Credentials sent to the backend. Both kinds can be set at once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthConfig {
+    pub api_key: Option<Secret>,
+    pub api_key_header: String,
+    pub bearer_token: Option<Secret>,
+    /// Program and arguments that print a fresh bearer token on stdout, e.g.
+    /// `gcloud auth print-identity-token`. Run when there is no token yet and
+    /// whenever the backend rejects the current one.
+    pub token_command: Vec<String>,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig {
+            api_key: None,
+            api_key_header: DEFAULT_API_KEY_HEADER.to_string(),
+            bearer_token: None,
+            token_command: Vec::new(),
+        }
+    }
+}
+
+/// Source of new bearer tokens, asked when the backend answers 401.
+#[async_trait]
+pub trait TokenRefresher: Send + Sync {
+    async fn refresh(&self) -> Result<Secret, ApiError>;
+}
+
+/// Takes the trimmed stdout of a command as the new token.
+#[derive(Debug, Clone)]
+pub struct CommandRefresher {
+    program: String,
+    args: Vec<String>,
+}
+
+impl CommandRefresher {
+    /// `None` for an empty command.
+    pub fn new(command: &[String]) -> Option<Self> {
+        let (program, args) = command.split_first()?;
+        Some(CommandRefresher {
+            program: program.clone(),
+            args: args.to_vec(),
+        })
+    }
+}
+
+#[async_trait]
+impl TokenRefresher for CommandRefresher {
+    async fn refresh(&self) -> Result<Secret, ApiError> {
+        // stderr is dropped: credential helpers may echo secrets there
+        let output = tokio::process::Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| {
+                ApiError::Unauthorized(format!(
+                    "cannot run token command '{}': {}",
+                    self.program, e
+                ))
+            })?;
+        if !output.status.success() {
+            return Err(ApiError::Unauthorized(format!(
+                "token command '{}' failed: {}",
+                self.program, output.status
+            )));
+        }
+        let token = String::from_utf8(output.stdout).unwrap_or_default();
+        let token = token.trim();
+        if token.is_empty() {
+            return Err(ApiError::Unauthorized(format!(
+                "token command '{}' printed no token",
+                self.program
+            )));
+        }
+        Ok(Secret::new(token))
+    }
+}
+
+/// Adds the configured credentials to every request and swaps the bearer
+/// token when the backend rejects it. Clones hold the same token, so a
+/// refresh through one is seen by every copy of the client.
+///
+/// Header values are marked sensitive, so reqwest leaves them out of its
+/// `Debug` output.
+#[derive(Clone, Default)]
+pub struct Authenticator {
+    api_key: Option<(HeaderName, HeaderValue)>,
+    bearer: Arc<RwLock<Bearer>>,
+    refresher: Option<Arc<dyn TokenRefresher>>,
+    refreshing: Arc<Mutex<()>>,
+}
+
+impl fmt::Debug for Authenticator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Authenticator")
+            .field("api_key", &self.api_key.as_ref().map(|(name, _)| name))
+            .field("bearer", &self.read_bearer().value.is_some())
+            .field("refresher", &self.refresher.is_some())
+            .finish()
+    }
+}
+
+impl Authenticator {
+    pub fn new(config: &AuthConfig) -> Result<Self, Box<dyn Error>> {
+        let api_key = match &config.api_key {
+            Some(key) => {
+                let name =
+                    HeaderName::from_bytes(config.api_key_header.as_bytes()).map_err(|e| {
+                        format!("Invalid API key header '{}': {}", config.api_key_header, e)
+                    })?;
+                let value = sensitive(key.expose())
+                    .ok_or("The API key contains characters not allowed in a header")?;
+                Some((name, value))
+            }
+            None => None,
+        };
+        let bearer = match &config.bearer_token {
+            Some(token) => Some(
+                bearer_value(token)
+                    .ok_or("The bearer token contains characters not allowed in a header")?,
+            ),
+            None => None,
+        };
+        let refresher = CommandRefresher::new(&config.token_command)
+            .map(|command| Arc::new(command) as Arc<dyn TokenRefresher>);
+        Ok(Authenticator {
+            api_key,
+            bearer: Arc::new(RwLock::new(Bearer {
+                value: bearer,
+                generation: 0,
+            })),
+            refresher,
+            ..Default::default()
+        })
+    }
+
+    /// Replaces the refresh hook, e.g. with one backed by an OAuth client.
+    pub fn with_refresher(mut self, refresher: Arc<dyn TokenRefresher>) -> Self {
+        self.refresher = Some(refresher);
+        self
+    }
+
+    pub fn can_refresh(&self) -> bool {
+        self.refresher.is_some()
+    }
+
+    /// Adds the credentials to `request`, fetching a first token if a refresh
+    /// hook is set but no token is. Also returns the token generation used,
+    /// to pass to [`Authenticator::refresh`] if the request is rejected.
+    pub async fn authorize(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<(RequestBuilder, u64), ApiError> {
+        let mut bearer = self.read_bearer();
+        if self.refresher.is_some() && bearer.value.is_none() {
+            self.refresh(bearer.generation).await?;
+            bearer = self.read_bearer();
+        }
+        let mut request = request;
+        if let Some((name, value)) = &self.api_key {
+            request = request.header(name, value);
+        }
+        if let Some(value) = bearer.value {
+            request = request.header(AUTHORIZATION, value);
+        }
+        Ok((request, bearer.generation))
+    }
+
+    /// Fetches a new token unless another caller already replaced the one of
+    /// `generation`. Returns whether a refresh hook is configured.
+    pub async fn refresh(&self, generation: u64) -> Result<bool, ApiError> {
+        let Some(refresher) = &self.refresher else {
+            return Ok(false);
+        };
+        let _guard = self.refreshing.lock().await;
+        if self.read_bearer().generation != generation {
+            return Ok(true);
+        }
+        let token = refresher.refresh().await?;
+        let value = bearer_value(&token).ok_or_else(|| {
+            ApiError::Unauthorized(
+                "the new token contains characters not allowed in a header".to_string(),
+            )
+        })?;
+        let mut bearer = write(&self.bearer);
+        bearer.value = Some(value);
+        bearer.generation += 1;
+        Ok(true)
+    }
+
+    /// Fetches a new token even if the current one was never rejected.
+    pub async fn reauthenticate(&self) -> Result<bool, ApiError> {
+        self.refresh(self.read_bearer().generation).await
+    }
+
+    fn read_bearer(&self) -> Bearer {
+        read(&self.bearer).clone()
+    }
+}
+
+/// The current `Authorization` value and how many times it was replaced, so
+/// concurrent 401s refresh only once.
+#[derive(Debug, Clone, Default)]
+struct Bearer {
+    value: Option<HeaderValue>,
+    generation: u64,
+}
+
+fn bearer_value(token: &Secret) -> Option<HeaderValue> {
+    sensitive(&format!("Bearer {}", token.expose()))
+}
+
+fn sensitive(value: &str) -> Option<HeaderValue> {
+    let mut value = HeaderValue::from_str(value).ok()?;
+    value.set_sensitive(true);
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    /// Hands out `token-1`, `token-2`, ... after a short delay.
+    #[derive(Default)]
+    struct CountingRefresher {
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl TokenRefresher for CountingRefresher {
+        async fn refresh(&self) -> Result<Secret, ApiError> {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(Secret::new(format!("token-{}", call)))
+        }
+    }
+
+    fn authenticator(token: Option<&str>) -> (Arc<CountingRefresher>, Authenticator) {
+        let refresher = Arc::new(CountingRefresher::default());
+        let config = AuthConfig {
+            bearer_token: token.map(Secret::new),
+            ..AuthConfig::default()
+        };
+        let auth = Authenticator::new(&config)
+            .unwrap()
+            .with_refresher(refresher.clone());
+        (refresher, auth)
+    }
+
+    async fn bearer(auth: &Authenticator) -> (String, u64) {
+        let request = reqwest::Client::new().get("http://localhost/");
+        let (request, generation) = auth.authorize(request).await.unwrap();
+        let request = request.build().unwrap();
+        let value = request.headers()[AUTHORIZATION]
+            .to_str()
+            .unwrap()
+            .to_string();
+        (value, generation)
+    }
+
+    #[tokio::test]
+    async fn concurrent_rejections_refresh_once() {
+        let (refresher, auth) = authenticator(Some("old"));
+        assert_eq!(bearer(&auth).await, ("Bearer old".to_string(), 0));
+
+        let (first, second) = tokio::join!(auth.refresh(0), auth.refresh(0));
+        assert_eq!((first, second), (Ok(true), Ok(true)));
+        assert_eq!(refresher.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(bearer(&auth).await, ("Bearer token-1".to_string(), 1));
+
+        // A late rejection of the old token doesn't replace the new one
+        auth.refresh(0).await.unwrap();
+        assert_eq!(refresher.calls.load(Ordering::SeqCst), 1);
+
+        auth.reauthenticate().await.unwrap();
+        assert_eq!(bearer(&auth).await, ("Bearer token-2".to_string(), 2));
+    }
+
+    #[tokio::test]
+    async fn first_token_is_fetched_on_demand() {
+        let (refresher, auth) = authenticator(None);
+        let clone = auth.clone();
+        assert_eq!(bearer(&auth).await, ("Bearer token-1".to_string(), 1));
+        // Clones share the token
+        assert_eq!(bearer(&clone).await, ("Bearer token-1".to_string(), 1));
+        assert_eq!(refresher.calls.load(Ordering::SeqCst), 1);
+
+        let without = Authenticator::new(&AuthConfig::default()).unwrap();
+        assert!(!without.can_refresh());
+        assert_eq!(without.refresh(0).await, Ok(false));
+    }
+
+    #[test]
+    fn secrets_are_never_printed() {
+        let config = AuthConfig {
+            api_key: Some(Secret::new("key-value")),
+            bearer_token: Some(Secret::new("token-value")),
+            ..AuthConfig::default()
+        };
+        let printed = format!("{:?} {:?}", config, Authenticator::new(&config).unwrap());
+        assert!(!printed.contains("key-value"), "{}", printed);
+        assert!(!printed.contains("token-value"), "{}", printed);
+    }
+}
@@ -0,0 +1,74 @@
+use super::apicalls::{GetByDestinationInput, GetByPriceRangeInput, TripApiClient};
+use super::error::ApiError;
+use super::page::Page;
+use super::trip::Trip;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Source of trip data used by the UI.
+///
+/// The HTTP client is one implementation; any other source (in-memory
+/// fixtures, local files) answers the same queries with the same shapes.
+#[async_trait]
+pub trait TripBackend: Send + Sync {
+    /// Human-readable description of the source, e.g. its URL or file path.
+    fn describe(&self) -> String;
+
+    /// Drops responses kept by caching layers; sources without a cache
+    /// ignore it.
+    fn clear_cache(&self) {}
+
+    /// Obtains new credentials after the backend rejected the current ones.
+    /// Returns `false` when the source can't renew them by itself.
+    async fn reauthenticate(&self) -> Result<bool, ApiError> {
+        Ok(false)
+    }
+
+    async fn is_alive(&self) -> Result<bool, ApiError>;
+
+    async fn get_by_index(&self, index: &str) -> Result<Trip, ApiError>;
+
+    async fn get_by_price_range(&self, data: &GetByPriceRangeInput)
+        -> Result<Page<Trip>, ApiError>;
+
+    async fn get_by_destination(
+        &self,
+        data: &GetByDestinationInput,
+    ) -> Result<Page<Trip>, ApiError>;
+}
+
+/// Backend handle shared with the UI through Dioxus context.
+pub type SharedBackend = Arc<dyn TripBackend>;
+
+#[async_trait]
+impl TripBackend for TripApiClient {
+    fn describe(&self) -> String {
+        self.base_url().to_string()
+    }
+
+    async fn reauthenticate(&self) -> Result<bool, ApiError> {
+        TripApiClient::reauthenticate(self).await
+    }
+
+    async fn is_alive(&self) -> Result<bool, ApiError> {
+        TripApiClient::is_alive(self).await
+    }
+
+    async fn get_by_index(&self, index: &str) -> Result<Trip, ApiError> {
+        TripApiClient::get_by_index(self, index).await
+    }
+
+    async fn get_by_price_range(
+        &self,
+        data: &GetByPriceRangeInput,
+    ) -> Result<Page<Trip>, ApiError> {
+        TripApiClient::get_by_price_range(self, data).await
+    }
+
+    async fn get_by_destination(
+        &self,
+        data: &GetByDestinationInput,
+    ) -> Result<Page<Trip>, ApiError> {
+        TripApiClient::get_by_destination(self, data).await
+    }
+}
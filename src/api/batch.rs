@@ -0,0 +1,37 @@
+use super::backend::SharedBackend;
+use super::error::ApiError;
+use super::trip::Trip;
+use crate::config::HttpConfig;
+use futures::stream::{self, Stream, StreamExt};
+
+/// Trips requested at the same time when the client has no request limit.
+const UNLIMITED_CONCURRENCY: usize = 8;
+
+/// Lookups worth having in flight with a client built from `http`: as many as
+/// it sends at once, since the rest would only wait in its queue.
+pub fn concurrency(http: &HttpConfig) -> usize {
+    match http.max_concurrent_requests {
+        0 => UNLIMITED_CONCURRENCY,
+        limit => limit,
+    }
+}
+
+/// Looks up every index with up to `concurrency` requests in flight.
+///
+/// Yields each index with its result in the order given, so a failure only
+/// affects its own entry. Dropping the stream stops it from sending the
+/// lookups not started yet; behind a
+/// [`CoalescingBackend`](super::coalesce::CoalescingBackend) those already
+/// sent still run to the end.
+pub fn get_by_indices(
+    backend: SharedBackend,
+    indices: Vec<u32>,
+    concurrency: usize,
+) -> impl Stream<Item = (u32, Result<Trip, ApiError>)> {
+    stream::iter(indices)
+        .map(move |index| {
+            let backend = backend.clone();
+            async move { (index, backend.get_by_index(&index.to_string()).await) }
+        })
+        .buffered(concurrency.max(1))
+}
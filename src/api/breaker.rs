@@ -0,0 +1,375 @@
+use super::apicalls::{GetByDestinationInput, GetByPriceRangeInput};
+use super::backend::{SharedBackend, TripBackend};
+use super::error::ApiError;
+use super::page::Page;
+use super::sync::lock;
+use super::trip::Trip;
+use async_trait::async_trait;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// Settings for the circuit breaker around the backend.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BreakerConfig {
+    pub enabled: bool,
+    /// Consecutive failed calls that open the circuit.
+    pub failure_threshold: u32,
+    /// Time the circuit stays open before a probe call is let through.
+    pub cooldown: Duration,
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        BreakerConfig {
+            enabled: true,
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls go through; failures are counted.
+    Closed,
+    /// Calls fail at once with [`ApiError::Unavailable`] until the cooldown
+    /// ends.
+    Open,
+    /// The cooldown ended: the next call is a probe that closes the circuit
+    /// if it succeeds and opens it again if it fails. Other calls fail at
+    /// once while the probe runs.
+    HalfOpen,
+}
+
+/// Snapshot of the breaker, for the UI.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BreakerStatus {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    /// End of the cooldown while the circuit is open.
+    pub retry_at: Option<Instant>,
+    /// Whether a probe call is running.
+    pub probing: bool,
+    /// Error of the last failed call, cleared once one succeeds.
+    pub last_error: Option<ApiError>,
+}
+
+impl BreakerStatus {
+    /// Time left until a probe is let through.
+    pub fn retry_in(&self) -> Option<Duration> {
+        self.retry_at
+            .map(|at| at.saturating_duration_since(Instant::now()))
+    }
+}
+
+#[derive(Debug, Default)]
+struct Circuit {
+    consecutive_failures: u32,
+    /// Set while the circuit is open or half-open.
+    open_until: Option<Instant>,
+    probing: bool,
+    last_error: Option<ApiError>,
+}
+
+impl Circuit {
+    fn state(&self, now: Instant) -> CircuitState {
+        match self.open_until {
+            None => CircuitState::Closed,
+            Some(until) if now < until => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    config: BreakerConfig,
+    circuit: Mutex<Circuit>,
+    changed: watch::Sender<u64>,
+}
+
+/// Stops calling a failing backend for a while, so each query fails at once
+/// instead of waiting for its timeouts.
+///
+/// Only outages count as failures (see [`ApiError::is_outage`]); a 404 or a
+/// rejected credential is an answer and resets the count like a success.
+/// The decorator and the header badge hold clones of the same breaker.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    shared: Arc<Shared>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: BreakerConfig) -> Self {
+        CircuitBreaker {
+            shared: Arc::new(Shared {
+                config,
+                circuit: Mutex::new(Circuit::default()),
+                changed: watch::channel(0).0,
+            }),
+        }
+    }
+
+    pub fn status(&self) -> BreakerStatus {
+        let circuit = self.lock();
+        let state = circuit.state(Instant::now());
+        BreakerStatus {
+            state,
+            consecutive_failures: circuit.consecutive_failures,
+            retry_at: circuit.open_until.filter(|_| state == CircuitState::Open),
+            probing: circuit.probing,
+            last_error: circuit.last_error.clone(),
+        }
+    }
+
+    /// Ticks every time the state or the failure count changes. The end of
+    /// a cooldown isn't announced; [`BreakerStatus::retry_at`] tells when.
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.shared.changed.subscribe()
+    }
+
+    /// Ends the cooldown now, so the next call probes the backend.
+    pub fn probe_now(&self) {
+        let mut circuit = self.lock();
+        if circuit.open_until.is_some() {
+            circuit.open_until = Some(Instant::now());
+            drop(circuit);
+            self.notify();
+        }
+    }
+
+    /// Runs `call` unless the circuit is open, and counts its outcome.
+    pub async fn call<T, F>(&self, call: F) -> Result<T, ApiError>
+    where
+        F: Future<Output = Result<T, ApiError>>,
+    {
+        let mut permit = self.acquire()?;
+        let result = call.await;
+        permit.finish(result.as_ref().err());
+        result
+    }
+
+    fn acquire(&self) -> Result<Permit<'_>, ApiError> {
+        let mut circuit = self.lock();
+        let now = Instant::now();
+        match circuit.state(now) {
+            CircuitState::Closed => Ok(Permit {
+                breaker: self,
+                probe: false,
+                finished: false,
+            }),
+            CircuitState::Open => Err(ApiError::Unavailable {
+                retry_in_secs: circuit.open_until.map_or(0, |until| {
+                    until.saturating_duration_since(now).as_secs() + 1
+                }),
+            }),
+            CircuitState::HalfOpen if circuit.probing => {
+                Err(ApiError::Unavailable { retry_in_secs: 0 })
+            }
+            CircuitState::HalfOpen => {
+                circuit.probing = true;
+                drop(circuit);
+                self.notify();
+                tracing::info!("circuit half-open, probing the backend");
+                Ok(Permit {
+                    breaker: self,
+                    probe: true,
+                    finished: false,
+                })
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Circuit> {
+        lock(&self.shared.circuit)
+    }
+
+    fn notify(&self) {
+        self.shared.changed.send_modify(|version| *version += 1);
+    }
+}
+
+/// Permission for one call. A probe dropped before finishing, e.g. because
+/// the query was cancelled, proved nothing, so the circuit opens again for
+/// another cooldown.
+struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    probe: bool,
+    finished: bool,
+}
+
+impl Permit<'_> {
+    fn finish(&mut self, error: Option<&ApiError>) {
+        self.finished = true;
+        let config = &self.breaker.shared.config;
+        let mut circuit = self.breaker.lock();
+        if self.probe {
+            circuit.probing = false;
+        }
+        match error {
+            Some(error) if error.is_outage() => {
+                circuit.consecutive_failures += 1;
+                circuit.last_error = Some(error.clone());
+                if self.probe || circuit.consecutive_failures >= config.failure_threshold {
+                    if circuit.open_until.is_none() || self.probe {
+                        tracing::warn!(
+                            failures = circuit.consecutive_failures,
+                            cooldown_secs = config.cooldown.as_secs(),
+                            "circuit opened"
+                        );
+                    }
+                    circuit.open_until = Some(Instant::now() + config.cooldown);
+                }
+            }
+            // Any answer shows the backend is reachable again
+            _ => {
+                if circuit.open_until.is_some() {
+                    tracing::info!("circuit closed");
+                }
+                circuit.consecutive_failures = 0;
+                circuit.open_until = None;
+                circuit.last_error = None;
+            }
+        }
+        drop(circuit);
+        self.breaker.notify();
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.probe && !self.finished {
+            let cooldown = self.breaker.shared.config.cooldown;
+            let mut circuit = self.breaker.lock();
+            circuit.probing = false;
+            circuit.open_until = Some(Instant::now() + cooldown);
+            drop(circuit);
+            tracing::warn!(
+                cooldown_secs = cooldown.as_secs(),
+                "probe cancelled, circuit opened"
+            );
+            self.breaker.notify();
+        }
+    }
+}
+
+/// Decorator that sends every query through a [`CircuitBreaker`].
+///
+/// Health checks go around it: they have their own timeout and status, and
+/// an answer from `/` says nothing about whether the trip endpoints work.
+pub struct CircuitBreakerBackend {
+    inner: SharedBackend,
+    breaker: CircuitBreaker,
+}
+
+impl CircuitBreakerBackend {
+    pub fn new(inner: SharedBackend, breaker: CircuitBreaker) -> Self {
+        CircuitBreakerBackend { inner, breaker }
+    }
+}
+
+#[async_trait]
+impl TripBackend for CircuitBreakerBackend {
+    fn describe(&self) -> String {
+        self.inner.describe()
+    }
+
+    fn clear_cache(&self) {
+        self.inner.clear_cache();
+    }
+
+    async fn reauthenticate(&self) -> Result<bool, ApiError> {
+        self.inner.reauthenticate().await
+    }
+
+    async fn is_alive(&self) -> Result<bool, ApiError> {
+        self.inner.is_alive().await
+    }
+
+    async fn get_by_index(&self, index: &str) -> Result<Trip, ApiError> {
+        self.breaker.call(self.inner.get_by_index(index)).await
+    }
+
+    async fn get_by_price_range(
+        &self,
+        data: &GetByPriceRangeInput,
+    ) -> Result<Page<Trip>, ApiError> {
+        self.breaker.call(self.inner.get_by_price_range(data)).await
+    }
+
+    async fn get_by_destination(
+        &self,
+        data: &GetByDestinationInput,
+    ) -> Result<Page<Trip>, ApiError> {
+        self.breaker.call(self.inner.get_by_destination(data)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(cooldown: Duration) -> CircuitBreaker {
+        CircuitBreaker::new(BreakerConfig {
+            enabled: true,
+            failure_threshold: 2,
+            cooldown,
+        })
+    }
+
+    async fn fail(breaker: &CircuitBreaker) -> Result<(), ApiError> {
+        breaker.call(async { Err(ApiError::Timeout) }).await
+    }
+
+    #[tokio::test]
+    async fn opens_after_threshold_and_closes_after_probe() {
+        let breaker = breaker(Duration::from_secs(60));
+        fail(&breaker).await.unwrap_err();
+        assert_eq!(breaker.status().state, CircuitState::Closed);
+        fail(&breaker).await.unwrap_err();
+        assert_eq!(breaker.status().state, CircuitState::Open);
+
+        let rejected = breaker.call(async { Ok(()) }).await;
+        assert!(matches!(rejected, Err(ApiError::Unavailable { .. })));
+
+        breaker.probe_now();
+        assert_eq!(breaker.status().state, CircuitState::HalfOpen);
+        breaker.call(async { Ok(()) }).await.unwrap();
+        let status = breaker.status();
+        assert_eq!(status.state, CircuitState::Closed);
+        assert_eq!(status.consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn answers_are_not_failures() {
+        let breaker = breaker(Duration::from_secs(60));
+        for _ in 0..3 {
+            let result: Result<(), _> = breaker
+                .call(async { Err(ApiError::NotFound("5".to_string())) })
+                .await;
+            result.unwrap_err();
+        }
+        assert_eq!(breaker.status().state, CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn cancelled_probe_opens_the_circuit_again() {
+        let breaker = breaker(Duration::from_secs(60));
+        fail(&breaker).await.unwrap_err();
+        fail(&breaker).await.unwrap_err();
+        breaker.probe_now();
+
+        let probe = breaker.call(std::future::pending::<Result<(), ApiError>>());
+        let cancelled = tokio::time::timeout(Duration::from_millis(10), probe).await;
+        assert!(cancelled.is_err());
+
+        let status = breaker.status();
+        assert_eq!(status.state, CircuitState::Open);
+        assert!(!status.probing);
+        breaker.probe_now();
+        breaker.call(async { Ok(()) }).await.unwrap();
+        assert_eq!(breaker.status().state, CircuitState::Closed);
+    }
+}
@@ -0,0 +1,345 @@
+use super::apicalls::{GetByDestinationInput, GetByPriceRangeInput};
+use super::backend::{SharedBackend, TripBackend};
+use super::error::ApiError;
+use super::page::{Page, PageRequest};
+use super::sync::lock;
+use super::trip::Trip;
+use async_trait::async_trait;
+use lru::LruCache;
+use rust_decimal::Decimal;
+use std::fmt;
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Settings for the in-memory response cache.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    /// How long a response is served before it is fetched again.
+    pub ttl: Duration,
+    /// Responses kept at most; the least recently used one is evicted first.
+    pub max_entries: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            enabled: true,
+            ttl: Duration::from_secs(300),
+            max_entries: 200,
+        }
+    }
+}
+
+/// Endpoint plus normalized query, so `"10"` and `"10.00 "` share an entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CacheKey {
+    Index(String),
+    PriceRange {
+        min: String,
+        max: String,
+        paging: PageRequest,
+    },
+    Destination {
+        destination: String,
+        paging: PageRequest,
+    },
+}
+
+impl CacheKey {
+    pub fn index(index: &str) -> Self {
+        CacheKey::Index(normalize_integer(index))
+    }
+
+    pub fn price_range(input: &GetByPriceRangeInput) -> Self {
+        CacheKey::PriceRange {
+            min: normalize_decimal(&input.min),
+            max: normalize_decimal(&input.max),
+            paging: input.paging,
+        }
+    }
+
+    pub fn destination(input: &GetByDestinationInput) -> Self {
+        CacheKey::Destination {
+            destination: normalize_integer(&input.destination),
+            paging: input.paging,
+        }
+    }
+}
+
+/// Rendered like the request path, e.g. `trip/price?min=10&max=20&page=1&per_page=5`.
+impl fmt::Display for CacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheKey::Index(index) => write!(f, "trip/{}", index),
+            CacheKey::PriceRange { min, max, paging } => write!(
+                f,
+                "trip/price?min={}&max={}&page={}&per_page={}",
+                min, max, paging.page, paging.per_page
+            ),
+            CacheKey::Destination {
+                destination,
+                paging,
+            } => write!(
+                f,
+                "trip/destination/{}?page={}&per_page={}",
+                destination, paging.page, paging.per_page
+            ),
+        }
+    }
+}
+
+fn normalize_integer(raw: &str) -> String {
+    let raw = raw.trim();
+    raw.parse::<u64>()
+        .map(|n| n.to_string())
+        .unwrap_or_else(|_| raw.to_string())
+}
+
+fn normalize_decimal(raw: &str) -> String {
+    let raw = raw.trim();
+    raw.parse::<Decimal>()
+        .map(|d| d.normalize().to_string())
+        .unwrap_or_else(|_| raw.to_string())
+}
+
+#[derive(Debug, Clone)]
+enum CachedValue {
+    Trip(Box<Trip>),
+    Page(Page<Trip>),
+}
+
+/// TTL + LRU map of successful responses.
+#[derive(Debug)]
+pub struct ResponseCache {
+    ttl: Duration,
+    entries: Mutex<LruCache<CacheKey, (Instant, CachedValue)>>,
+}
+
+impl ResponseCache {
+    pub fn new(config: &CacheConfig) -> Self {
+        let capacity = NonZeroUsize::new(config.max_entries).unwrap_or(NonZeroUsize::MIN);
+        ResponseCache {
+            ttl: config.ttl,
+            entries: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Returns a fresh entry with its age; expired entries are dropped.
+    fn get(&self, key: &CacheKey) -> Option<(Duration, CachedValue)> {
+        let mut entries = lock(&self.entries);
+        let age = entries.get(key)?.0.elapsed();
+        if age > self.ttl {
+            entries.pop(key);
+            return None;
+        }
+        entries.get(key).map(|(_, value)| (age, value.clone()))
+    }
+
+    fn put(&self, key: CacheKey, value: CachedValue) {
+        let mut entries = lock(&self.entries);
+        entries.put(key, (Instant::now(), value));
+    }
+
+    pub fn clear(&self) {
+        lock(&self.entries).clear();
+    }
+}
+
+tokio::task_local! {
+    static BYPASS: ();
+}
+
+/// Runs `future` skipping cached responses, e.g. for a manual refresh. The
+/// fresh responses still replace what was cached.
+pub async fn bypass_cache<F: Future>(future: F) -> F::Output {
+    BYPASS.scope((), future).await
+}
+
+fn bypassing() -> bool {
+    BYPASS.try_with(|_| ()).is_ok()
+}
+
+/// Decorator that serves repeated queries from a [`ResponseCache`].
+///
+/// Trips and pages served from the cache carry their age in
+/// [`Trip::cache_age`] and [`Page::cache_age`].
+pub struct CachedBackend {
+    inner: SharedBackend,
+    cache: ResponseCache,
+}
+
+impl CachedBackend {
+    pub fn new(inner: SharedBackend, config: &CacheConfig) -> Self {
+        CachedBackend {
+            inner,
+            cache: ResponseCache::new(config),
+        }
+    }
+
+    async fn page<F>(&self, key: CacheKey, fetch: F) -> Result<Page<Trip>, ApiError>
+    where
+        F: Future<Output = Result<Page<Trip>, ApiError>>,
+    {
+        if !bypassing() {
+            if let Some((age, CachedValue::Page(mut page))) = self.cache.get(&key) {
+                page.cache_age = Some(age);
+                return Ok(page);
+            }
+        }
+        let page = fetch.await?;
+        // Stale copies served by a fallback layer are not worth keeping
+        if page.cache_age.is_none() {
+            self.cache.put(key, CachedValue::Page(page.clone()));
+        }
+        Ok(page)
+    }
+}
+
+#[async_trait]
+impl TripBackend for CachedBackend {
+    fn describe(&self) -> String {
+        format!("{} (con caché)", self.inner.describe())
+    }
+
+    fn clear_cache(&self) {
+        self.cache.clear();
+        self.inner.clear_cache();
+    }
+
+    async fn reauthenticate(&self) -> Result<bool, ApiError> {
+        self.inner.reauthenticate().await
+    }
+
+    async fn is_alive(&self) -> Result<bool, ApiError> {
+        self.inner.is_alive().await
+    }
+
+    async fn get_by_index(&self, index: &str) -> Result<Trip, ApiError> {
+        let key = CacheKey::index(index);
+        if !bypassing() {
+            if let Some((age, CachedValue::Trip(mut trip))) = self.cache.get(&key) {
+                trip.cache_age = Some(age);
+                return Ok(*trip);
+            }
+        }
+        let trip = self.inner.get_by_index(index).await?;
+        if trip.cache_age.is_none() {
+            self.cache
+                .put(key, CachedValue::Trip(Box::new(trip.clone())));
+        }
+        Ok(trip)
+    }
+
+    async fn get_by_price_range(
+        &self,
+        data: &GetByPriceRangeInput,
+    ) -> Result<Page<Trip>, ApiError> {
+        self.page(
+            CacheKey::price_range(data),
+            self.inner.get_by_price_range(data),
+        )
+        .await
+    }
+
+    async fn get_by_destination(
+        &self,
+        data: &GetByDestinationInput,
+    ) -> Result<Page<Trip>, ApiError> {
+        self.page(
+            CacheKey::destination(data),
+            self.inner.get_by_destination(data),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_support::{trip, TestBackend};
+    use std::sync::Arc;
+
+    fn cached(config: CacheConfig) -> (Arc<TestBackend>, CachedBackend) {
+        let trips = (1..=3).map(|index| trip(index, 1, "10")).collect();
+        let source = Arc::new(TestBackend::new(trips));
+        let backend = CachedBackend::new(source.clone(), &config);
+        (source, backend)
+    }
+
+    #[test]
+    fn equivalent_queries_share_a_key() {
+        assert_eq!(CacheKey::index(" 05"), CacheKey::index("5"));
+        assert_ne!(CacheKey::index("5"), CacheKey::index("50"));
+
+        let price = |min: &str, max: &str| {
+            CacheKey::price_range(&GetByPriceRangeInput {
+                min: min.to_string(),
+                max: max.to_string(),
+                paging: PageRequest::default(),
+            })
+        };
+        assert_eq!(price("10", "20.5"), price("10.00 ", " 20.50"));
+        assert_ne!(price("10", "20"), price("10", "21"));
+        // Text that isn't a number is kept, trimmed
+        assert_eq!(price(" abc", "20"), price("abc", "20.0"));
+
+        let key = CacheKey::destination(&GetByDestinationInput {
+            destination: "0142".to_string(),
+            paging: PageRequest::new(2, 5),
+        });
+        assert_eq!(key.to_string(), "trip/destination/142?page=2&per_page=5");
+        assert_eq!(
+            price("10.0", "20").to_string(),
+            "trip/price?min=10&max=20&page=1&per_page=10"
+        );
+    }
+
+    #[tokio::test]
+    async fn entries_expire_after_the_ttl() {
+        let (source, backend) = cached(CacheConfig {
+            ttl: Duration::from_millis(50),
+            ..CacheConfig::default()
+        });
+
+        assert_eq!(backend.get_by_index("1").await.unwrap().cache_age, None);
+        let hit = backend.get_by_index("01").await.unwrap();
+        assert!(hit.cache_age.is_some());
+        assert_eq!(source.started(), 1);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(backend.get_by_index("1").await.unwrap().cache_age, None);
+        assert_eq!(source.started(), 2);
+
+        // A refresh skips the cache but still stores the answer
+        bypass_cache(backend.get_by_index("1")).await.unwrap();
+        assert_eq!(source.started(), 3);
+        backend.get_by_index("1").await.unwrap();
+        assert_eq!(source.started(), 3);
+    }
+
+    #[tokio::test]
+    async fn least_recently_used_entry_is_evicted() {
+        let (source, backend) = cached(CacheConfig {
+            max_entries: 2,
+            ..CacheConfig::default()
+        });
+        for index in ["1", "2", "1", "3"] {
+            backend.get_by_index(index).await.unwrap();
+        }
+        assert_eq!(source.started(), 3);
+
+        // 2 was the least recently used when 3 came in
+        backend.get_by_index("1").await.unwrap();
+        assert_eq!(source.started(), 3);
+        backend.get_by_index("2").await.unwrap();
+        assert_eq!(source.started(), 4);
+
+        // Errors are never cached
+        backend.get_by_index("9").await.unwrap_err();
+        backend.get_by_index("9").await.unwrap_err();
+        assert_eq!(source.started(), 6);
+    }
+}
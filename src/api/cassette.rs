@@ -0,0 +1,304 @@
+use super::error::ApiError;
+use super::sync::lock;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Whether a [`Cassette`] is being filled from the backend or played back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Requests go to the backend and every response is saved.
+    Record,
+    /// Requests never leave the process; each one is answered with its
+    /// recorded response, and an unknown one fails with
+    /// [`ApiError::Cassette`].
+    Replay,
+}
+
+/// Cassette file and mode, from `--record` or `--replay`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CassetteConfig {
+    pub mode: CassetteMode,
+    pub path: PathBuf,
+}
+
+/// One request and the response the backend gave to it. Headers are left
+/// out, so credentials never reach the file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    pub path: String,
+    #[serde(default)]
+    pub query: BTreeMap<String, String>,
+    pub status: u16,
+    /// Body of JSON responses, stored as JSON so fixtures read and diff well.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json: Option<serde_json::Value>,
+    /// Body of any other response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+impl Interaction {
+    fn matches(&self, method: &str, path: &str, query: &BTreeMap<String, String>) -> bool {
+        self.method == method && self.path == path && &self.query == query
+    }
+
+    fn body(&self) -> Vec<u8> {
+        match (&self.json, &self.text) {
+            (Some(json), _) => serde_json::to_vec(json).unwrap_or_default(),
+            (None, Some(text)) => text.clone().into_bytes(),
+            (None, None) => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CassetteFile {
+    interactions: Vec<Interaction>,
+}
+
+/// Request/response pairs of the trip endpoints saved to a JSON file, to run
+/// the client against a fixed backend without network access.
+///
+/// Record once against the real backend, then replay in every later run:
+///
+/// ```
+/// # use frontend_local_rust_os::api::apicalls::TripApiClient;
+/// # use frontend_local_rust_os::api::cassette::Cassette;
+/// # use frontend_local_rust_os::config::AppConfig;
+/// # use std::sync::Arc;
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let cassette = Arc::new(Cassette::replay("fixtures/cassettes/trips.json")?);
+/// let client = TripApiClient::from_config(&AppConfig::default())?.with_cassette(cassette.clone());
+/// let trip = client.get_by_index("5").await?;
+/// assert_eq!(trip.total_amount.raw(), "11.20");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Requests match on method, path and query parameters, whatever the base
+/// URL. A request with no recording fails with the parameters that differ
+/// from the closest recorded one.
+#[derive(Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    mode: CassetteMode,
+    interactions: Mutex<Vec<Interaction>>,
+    /// Positions of the interactions replayed so far.
+    played: Mutex<BTreeSet<usize>>,
+}
+
+impl Cassette {
+    pub fn open(config: &CassetteConfig) -> Result<Self, Box<dyn Error>> {
+        match config.mode {
+            CassetteMode::Record => Self::record(&config.path),
+            CassetteMode::Replay => Self::replay(&config.path),
+        }
+    }
+
+    /// Records into `path`. An existing file is extended, and a request
+    /// recorded again replaces its previous response.
+    pub fn record(path: impl Into<PathBuf>) -> Result<Self, Box<dyn Error>> {
+        let path = path.into();
+        let file = if path.exists() {
+            read_file(&path)?
+        } else {
+            CassetteFile::default()
+        };
+        Ok(Self::new(path, CassetteMode::Record, file))
+    }
+
+    pub fn replay(path: impl Into<PathBuf>) -> Result<Self, Box<dyn Error>> {
+        let path = path.into();
+        let file = read_file(&path)?;
+        Ok(Self::new(path, CassetteMode::Replay, file))
+    }
+
+    fn new(path: PathBuf, mode: CassetteMode, file: CassetteFile) -> Self {
+        Cassette {
+            path,
+            mode,
+            interactions: Mutex::new(file.interactions),
+            played: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn interactions(&self) -> Vec<Interaction> {
+        lock(&self.interactions).clone()
+    }
+
+    /// Recorded interactions no request asked for during the replay.
+    pub fn unplayed(&self) -> Vec<Interaction> {
+        let played = lock(&self.played);
+        lock(&self.interactions)
+            .iter()
+            .enumerate()
+            .filter(|(position, _)| !played.contains(position))
+            .map(|(_, interaction)| interaction.clone())
+            .collect()
+    }
+
+    /// Status and body recorded for the request.
+    pub fn play(&self, method: &str, url: &Url) -> Result<(u16, Vec<u8>), ApiError> {
+        let query = query_map(url);
+        let interactions = lock(&self.interactions);
+        let found = interactions
+            .iter()
+            .position(|interaction| interaction.matches(method, url.path(), &query));
+        match found {
+            Some(position) => {
+                lock(&self.played).insert(position);
+                let interaction = &interactions[position];
+                Ok((interaction.status, interaction.body()))
+            }
+            None => Err(ApiError::Cassette(self.mismatch(
+                &interactions,
+                method,
+                url,
+                &query,
+            ))),
+        }
+    }
+
+    /// Saves the response to the file right away, so an interrupted session
+    /// keeps what it recorded.
+    pub fn store(&self, method: &str, url: &Url, status: u16, body: &[u8]) -> Result<(), ApiError> {
+        let query = query_map(url);
+        let (json, text) = match serde_json::from_slice(body) {
+            Ok(json) => (Some(json), None),
+            Err(_) => (None, Some(String::from_utf8_lossy(body).into_owned())),
+        };
+        let interaction = Interaction {
+            method: method.to_string(),
+            path: url.path().to_string(),
+            query,
+            status,
+            json,
+            text,
+        };
+
+        let mut interactions = lock(&self.interactions);
+        let existing = interactions.iter_mut().find(|recorded| {
+            recorded.matches(&interaction.method, &interaction.path, &interaction.query)
+        });
+        match existing {
+            Some(recorded) => *recorded = interaction,
+            None => interactions.push(interaction),
+        }
+        let file = CassetteFile {
+            interactions: interactions.clone(),
+        };
+        write_file(&self.path, &file)
+            .map_err(|e| ApiError::Cassette(format!("cannot save {}: {}", self.path.display(), e)))
+    }
+
+    /// Explains why nothing matched, against the closest recording with the
+    /// same method and path.
+    fn mismatch(
+        &self,
+        interactions: &[Interaction],
+        method: &str,
+        url: &Url,
+        query: &BTreeMap<String, String>,
+    ) -> String {
+        let mut message = format!(
+            "no recorded response in {} for {} {}",
+            self.path.display(),
+            method,
+            describe_request(url.path(), query)
+        );
+        let closest = interactions
+            .iter()
+            .filter(|interaction| interaction.method == method && interaction.path == url.path())
+            .min_by_key(|interaction| query_diff(&interaction.query, query).len());
+        match closest {
+            Some(interaction) => {
+                message.push_str("\nclosest recording differs in the query parameters:");
+                for line in query_diff(&interaction.query, query) {
+                    message.push_str("\n  ");
+                    message.push_str(&line);
+                }
+            }
+            None => {
+                let recorded: BTreeSet<String> = interactions
+                    .iter()
+                    .map(|interaction| format!("{} {}", interaction.method, interaction.path))
+                    .collect();
+                message.push_str("\nnothing was recorded for that path; recorded paths:");
+                for request in recorded {
+                    message.push_str("\n  ");
+                    message.push_str(&request);
+                }
+            }
+        }
+        message
+    }
+}
+
+/// One line per parameter that differs between a recorded query and the
+/// requested one.
+fn query_diff(
+    recorded: &BTreeMap<String, String>,
+    requested: &BTreeMap<String, String>,
+) -> Vec<String> {
+    let names: BTreeSet<&String> = recorded.keys().chain(requested.keys()).collect();
+    names
+        .into_iter()
+        .filter_map(|name| match (recorded.get(name), requested.get(name)) {
+            (Some(old), Some(new)) if old == new => None,
+            (Some(old), Some(new)) => {
+                Some(format!("{}: recorded {:?}, requested {:?}", name, old, new))
+            }
+            (Some(old), None) => Some(format!("{}: recorded {:?}, not requested", name, old)),
+            (None, Some(new)) => Some(format!("{}: not recorded, requested {:?}", name, new)),
+            (None, None) => None,
+        })
+        .collect()
+}
+
+fn describe_request(path: &str, query: &BTreeMap<String, String>) -> String {
+    if query.is_empty() {
+        return path.to_string();
+    }
+    let params: Vec<String> = query
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect();
+    format!("{}?{}", path, params.join("&"))
+}
+
+fn query_map(url: &Url) -> BTreeMap<String, String> {
+    url.query_pairs().into_owned().collect()
+}
+
+fn read_file(path: &Path) -> Result<CassetteFile, Box<dyn Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read cassette {}: {}", path.display(), e))?;
+    let file = serde_json::from_str(&contents)
+        .map_err(|e| format!("Invalid cassette {}: {}", path.display(), e))?;
+    Ok(file)
+}
+
+/// Writes through a temporary file, so a crash never leaves half a cassette.
+fn write_file(path: &Path, file: &CassetteFile) -> std::io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_vec_pretty(file).map_err(std::io::Error::other)?;
+    let temp = path.with_extension("tmp");
+    std::fs::write(&temp, json)?;
+    std::fs::rename(&temp, path)
+}
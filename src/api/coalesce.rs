@@ -0,0 +1,265 @@
+use super::apicalls::{GetByDestinationInput, GetByPriceRangeInput};
+use super::backend::{SharedBackend, TripBackend};
+use super::cache::CacheKey;
+use super::error::ApiError;
+use super::page::Page;
+use super::retry::{forward_retries, report_retry, RetryEvent};
+use super::sync::lock;
+use super::trip::Trip;
+use async_trait::async_trait;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use std::collections::HashMap;
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+/// Retries of one call kept for callers that fall behind.
+const RETRY_EVENTS: usize = 16;
+
+/// A fetch running on its own task.
+#[derive(Clone)]
+struct Call<T> {
+    result: Shared<BoxFuture<'static, Result<T, ApiError>>>,
+    /// Its retries, which the callers' observers can't see from the task.
+    retries: broadcast::Sender<RetryEvent>,
+}
+
+/// Queries currently being fetched, by [`CacheKey`].
+struct InFlight<T> {
+    calls: Arc<Mutex<HashMap<CacheKey, Call<T>>>>,
+}
+
+impl<T: Clone + Send + Sync + 'static> InFlight<T> {
+    fn new() -> Self {
+        InFlight {
+            calls: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Joins the call in flight for `key`, or starts one with `fetch`.
+    ///
+    /// The fetch runs in its own task, so it finishes and frees its
+    /// concurrency slot and breaker probe even when every caller is
+    /// cancelled; the next caller after that starts a new one. Its retries
+    /// are reported to the [`observe_retries`] observer of every caller
+    /// waiting on it.
+    ///
+    /// [`observe_retries`]: super::retry::observe_retries
+    async fn run(
+        &self,
+        key: CacheKey,
+        fetch: impl FnOnce() -> BoxFuture<'static, Result<T, ApiError>>,
+    ) -> Result<T, ApiError> {
+        let (call, mut retries) = {
+            let mut calls = lock(&self.calls);
+            match calls.get(&key) {
+                Some(call) => {
+                    tracing::debug!(query = %key, "joining request in flight");
+                    (call.clone(), call.retries.subscribe())
+                }
+                None => {
+                    // Subscribed before the task can retry, and spawned with
+                    // the map locked so it can't remove its entry before it
+                    // is inserted
+                    let (events, retries) = broadcast::channel(RETRY_EVENTS);
+                    let fetch = forward_retries(events.clone(), fetch());
+                    let finished = self.calls.clone();
+                    let finished_key = key.clone();
+                    let task = tokio::spawn(async move {
+                        let result = fetch.await;
+                        lock(&finished).remove(&finished_key);
+                        result
+                    });
+                    let result = async move {
+                        match task.await {
+                            Ok(result) => result,
+                            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+                            Err(e) => Err(ApiError::Transport(e.to_string())),
+                        }
+                    }
+                    .boxed()
+                    .shared();
+                    let call = Call {
+                        result,
+                        retries: events,
+                    };
+                    calls.insert(key, call.clone());
+                    (call, retries)
+                }
+            }
+        };
+
+        let mut result = pin!(call.result);
+        loop {
+            tokio::select! {
+                // Retries first, so none is left unreported at the end
+                biased;
+                Ok(event) = retries.recv() => report_retry(&event),
+                result = &mut result => return result,
+            }
+        }
+    }
+}
+
+/// Decorator that sends identical queries issued while one is still in
+/// flight only once; every caller gets a clone of the same result.
+///
+/// Queries are identical when their [`CacheKey`] is, so `"10"` and
+/// `"10.00"` share a request like they share a cache entry. Health checks
+/// are passed through.
+pub struct CoalescingBackend {
+    inner: SharedBackend,
+    trips: InFlight<Trip>,
+    pages: InFlight<Page<Trip>>,
+}
+
+impl CoalescingBackend {
+    pub fn new(inner: SharedBackend) -> Self {
+        CoalescingBackend {
+            inner,
+            trips: InFlight::new(),
+            pages: InFlight::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl TripBackend for CoalescingBackend {
+    fn describe(&self) -> String {
+        self.inner.describe()
+    }
+
+    fn clear_cache(&self) {
+        self.inner.clear_cache();
+    }
+
+    async fn reauthenticate(&self) -> Result<bool, ApiError> {
+        self.inner.reauthenticate().await
+    }
+
+    async fn is_alive(&self) -> Result<bool, ApiError> {
+        self.inner.is_alive().await
+    }
+
+    async fn get_by_index(&self, index: &str) -> Result<Trip, ApiError> {
+        let inner = self.inner.clone();
+        let index = index.to_string();
+        self.trips
+            .run(CacheKey::index(&index), move || {
+                async move { inner.get_by_index(&index).await }.boxed()
+            })
+            .await
+    }
+
+    async fn get_by_price_range(
+        &self,
+        data: &GetByPriceRangeInput,
+    ) -> Result<Page<Trip>, ApiError> {
+        let inner = self.inner.clone();
+        let data = data.clone();
+        self.pages
+            .run(CacheKey::price_range(&data), move || {
+                async move { inner.get_by_price_range(&data).await }.boxed()
+            })
+            .await
+    }
+
+    async fn get_by_destination(
+        &self,
+        data: &GetByDestinationInput,
+    ) -> Result<Page<Trip>, ApiError> {
+        let inner = self.inner.clone();
+        let data = data.clone();
+        self.pages
+            .run(CacheKey::destination(&data), move || {
+                async move { inner.get_by_destination(&data).await }.boxed()
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::retry::{observe_retries, RetryPolicy};
+    use crate::api::test_support::{trip, TestBackend};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::sync::atomic::AtomicU32;
+    use std::time::Duration;
+
+    fn coalescing() -> (Arc<TestBackend>, Arc<CoalescingBackend>) {
+        let slow = Arc::new(TestBackend {
+            delay: Duration::from_millis(50),
+            ..TestBackend::new(Vec::new())
+        });
+        let backend = Arc::new(CoalescingBackend::new(slow.clone()));
+        (slow, backend)
+    }
+
+    #[tokio::test]
+    async fn identical_queries_share_one_call() {
+        let (slow, backend) = coalescing();
+        let results = futures::future::join_all(
+            ["5", "05", " 5", "6"].map(|index| backend.get_by_index(index)),
+        )
+        .await;
+
+        assert_eq!(slow.started(), 2);
+        assert!(matches!(results[0], Err(ApiError::NotFound(_))));
+        assert_eq!(results[1], results[0]);
+        assert_eq!(results[2], results[0]);
+    }
+
+    #[tokio::test]
+    async fn cancelled_call_still_finishes_and_is_forgotten() {
+        let (slow, backend) = coalescing();
+        let waiter = {
+            let backend = backend.clone();
+            tokio::spawn(async move { backend.get_by_index("7").await })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        waiter.abort();
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(slow.finished(), 1);
+        assert!(lock(&backend.trips.calls).is_empty());
+
+        // Nothing is left in flight, so the next lookup is sent again
+        backend.get_by_index("7").await.unwrap_err();
+        assert_eq!(slow.started(), 2);
+    }
+
+    #[tokio::test]
+    async fn retries_are_reported_to_every_caller() {
+        let flaky = Arc::new(TestBackend {
+            delay: Duration::from_millis(20),
+            failures: AtomicU32::new(2),
+            retry: RetryPolicy {
+                max_attempts: 3,
+                initial_backoff: Duration::from_millis(10),
+                jitter: false,
+                ..RetryPolicy::default()
+            },
+            ..TestBackend::new(vec![trip(5, 1, "10")])
+        });
+        let backend = CoalescingBackend::new(flaky.clone());
+
+        let first = Rc::new(RefCell::new(Vec::new()));
+        let second = Rc::new(RefCell::new(Vec::new()));
+        let record = |seen: &Rc<RefCell<Vec<u32>>>| {
+            let seen = seen.clone();
+            move |event: &RetryEvent| seen.borrow_mut().push(event.retry)
+        };
+        let (a, b) = tokio::join!(
+            observe_retries(record(&first), backend.get_by_index("5")),
+            observe_retries(record(&second), backend.get_by_index("5")),
+        );
+
+        assert_eq!(flaky.started(), 1);
+        assert_eq!(a.unwrap().index.raw(), "5");
+        assert_eq!(b.unwrap().index.raw(), "5");
+        assert_eq!(*first.borrow(), [1, 2]);
+        assert_eq!(*second.borrow(), [1, 2]);
+    }
+}
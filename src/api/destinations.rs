@@ -0,0 +1,234 @@
+use super::apicalls::GetByDestinationInput;
+use super::backend::TripBackend;
+use super::error::ApiError;
+use super::page::{Page, PageRequest, RejectedItem};
+use super::trip::Trip;
+use futures::stream::{self, StreamExt, TryStreamExt};
+
+/// Destinations queried at the same time.
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Trips to any of several destinations, e.g. every airport, listed as one
+/// result set: all trips to the first destination, then to the second, and
+/// so on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetByDestinationsInput {
+    pub destinations: Vec<String>,
+    pub paging: PageRequest,
+}
+
+/// Answers a multi-destination query with one `get_by_destination` call per
+/// destination and merges them into a single page.
+///
+/// The first page of every destination is requested up front to learn its
+/// total; then only the destination pages that overlap the requested page are
+/// fetched. `total` and `time_ms` add up those of every call, and `cache_age`
+/// is that of the oldest cached response used. A destination the backend
+/// doesn't know counts as having no trips.
+pub async fn get_by_destinations(
+    backend: &dyn TripBackend,
+    input: &GetByDestinationsInput,
+    concurrency: usize,
+) -> Result<Page<Trip>, ApiError> {
+    let concurrency = concurrency.max(1);
+    let paging = input.paging;
+    // A single destination is a plain query, with its own not found error
+    if let [destination] = input.destinations.as_slice() {
+        return backend
+            .get_by_destination(&GetByDestinationInput {
+                destination: destination.clone(),
+                paging,
+            })
+            .await;
+    }
+
+    let per_page = paging.per_page.max(1);
+    let first_request = PageRequest::first(per_page);
+    let firsts: Vec<Page<Trip>> =
+        stream::iter(input.destinations.clone())
+            .map(|destination| async move {
+                fetch_destination(backend, &destination, first_request).await
+            })
+            .buffered(concurrency)
+            .try_collect()
+            .await?;
+
+    // Position of each destination's trips within the merged list, and the
+    // slice of them that falls on the requested page
+    let start = paging.offset();
+    let end = start + per_page as usize;
+    let mut base = 0;
+    let mut windows = Vec::new();
+    for (position, first) in firsts.iter().enumerate() {
+        let total = first.total as usize;
+        let (from, to) = (start.max(base), end.min(base + total));
+        if from < to {
+            windows.push((position, base, from - base, to - base));
+        }
+        base += total;
+    }
+
+    let wanted: Vec<(usize, u32)> = windows
+        .iter()
+        .flat_map(|&(position, _, from, to)| {
+            let first_page = from / per_page as usize + 1;
+            let last_page = (to - 1) / per_page as usize + 1;
+            (first_page.max(2)..=last_page).map(move |page| (position, page as u32))
+        })
+        .collect();
+    let rest: Vec<(usize, Page<Trip>)> = stream::iter(wanted)
+        .map(|(position, page)| async move {
+            let destination = &input.destinations[position];
+            fetch_destination(backend, destination, first_request.with_page(page))
+                .await
+                .map(|page| (position, page))
+        })
+        .buffered(concurrency)
+        .try_collect()
+        .await?;
+
+    let mut items = Vec::with_capacity(per_page as usize);
+    let mut rejected = Vec::new();
+    for &(position, base, from, to) in &windows {
+        let pages = || {
+            std::iter::once(&firsts[position]).chain(
+                rest.iter()
+                    .filter(|(p, _)| *p == position)
+                    .map(|(_, page)| page),
+            )
+        };
+        let trips = pages().flat_map(|page| page.item_positions().zip(&page.items));
+        items.extend(
+            trips
+                .filter(|(i, _)| (from..to).contains(i))
+                .map(|(_, trip)| trip.clone()),
+        );
+        // Rejected records move to their position in the merged list
+        rejected.extend(
+            pages()
+                .flat_map(|page| &page.rejected)
+                .filter(|item| (from..to).contains(&item.position))
+                .map(|item| RejectedItem {
+                    position: base + item.position,
+                    ..item.clone()
+                }),
+        );
+    }
+
+    let total: u32 = firsts.iter().map(|page| page.total).sum();
+    let responses = || firsts.iter().chain(rest.iter().map(|(_, page)| page));
+    Ok(Page {
+        items,
+        page: paging.page,
+        pages: total.div_ceil(per_page),
+        per_page,
+        time_ms: responses().map(|page| page.time_ms).sum(),
+        total,
+        cache_age: responses().filter_map(|page| page.cache_age).max(),
+        rejected,
+    })
+}
+
+async fn fetch_destination(
+    backend: &dyn TripBackend,
+    destination: &str,
+    paging: PageRequest,
+) -> Result<Page<Trip>, ApiError> {
+    let input = GetByDestinationInput {
+        destination: destination.to_string(),
+        paging,
+    };
+    match backend.get_by_destination(&input).await {
+        Err(ApiError::NotFound(_)) => Ok(Page {
+            items: Vec::new(),
+            page: paging.page,
+            pages: 0,
+            per_page: paging.per_page,
+            time_ms: 0,
+            total: 0,
+            cache_age: None,
+            rejected: Vec::new(),
+        }),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_support::{trip, TestBackend};
+
+    /// Destination 1 has trips 10-12, 2 is unknown and 3 has trips 30-33,
+    /// with 31 unreadable: merged, 10 11 12 30 (31) 32 33.
+    fn airports() -> TestBackend {
+        let trips = [
+            (10, 1),
+            (11, 1),
+            (12, 1),
+            (30, 3),
+            (31, 3),
+            (32, 3),
+            (33, 3),
+        ]
+        .map(|(index, destination)| trip(index, destination, "10"));
+        TestBackend {
+            unknown_destinations: vec!["2".to_string()],
+            unreadable: vec![31],
+            ..TestBackend::new(trips.to_vec())
+        }
+    }
+
+    async fn merged(backend: &TestBackend, page: u32) -> Page<Trip> {
+        let input = GetByDestinationsInput {
+            destinations: ["1", "2", "3"].map(String::from).to_vec(),
+            paging: PageRequest::new(page, 2),
+        };
+        get_by_destinations(backend, &input, 2).await.unwrap()
+    }
+
+    fn indices(page: &Page<Trip>) -> Vec<&str> {
+        page.items.iter().map(|trip| trip.index.raw()).collect()
+    }
+
+    #[tokio::test]
+    async fn page_spans_a_destination_boundary() {
+        let backend = airports();
+        let page = merged(&backend, 2).await;
+
+        assert_eq!(indices(&page), ["12", "30"]);
+        assert_eq!((page.page, page.pages, page.total), (2, 4, 7));
+        assert_eq!(page.item_positions().collect::<Vec<_>>(), [2, 3]);
+        assert!(page.rejected.is_empty());
+        // The first page of each destination, plus the second of destination 1
+        assert_eq!(backend.started(), 4);
+    }
+
+    #[tokio::test]
+    async fn later_pages_and_rejected_records_move_to_their_merged_position() {
+        let backend = airports();
+        let page = merged(&backend, 3).await;
+
+        assert_eq!(indices(&page), ["32"]);
+        assert_eq!(page.rejected.len(), 1);
+        assert_eq!(page.rejected[0].position, 4);
+        assert!(page.rejected[0].raw.contains("31"));
+        assert_eq!(page.range_text(), "5-6 de 7");
+        // Plus the second page of destination 3
+        assert_eq!(backend.started(), 4);
+
+        let last = merged(&backend, 4).await;
+        assert_eq!(indices(&last), ["33"]);
+        assert!(!last.has_next());
+    }
+
+    #[tokio::test]
+    async fn unknown_destination_alone_is_not_found() {
+        let backend = airports();
+        let input = GetByDestinationsInput {
+            destinations: vec!["2".to_string()],
+            paging: PageRequest::first(2),
+        };
+        let result = get_by_destinations(&backend, &input, 2).await;
+        assert!(matches!(result, Err(ApiError::NotFound(_))), "{:?}", result);
+    }
+}
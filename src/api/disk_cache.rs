@@ -0,0 +1,403 @@
+use super::apicalls::{GetByDestinationInput, GetByPriceRangeInput};
+use super::backend::{SharedBackend, TripBackend};
+use super::cache::CacheKey;
+use super::error::ApiError;
+use super::page::Page;
+use super::trip::Trip;
+use async_trait::async_trait;
+use redb::{Database, ReadableTable, TableDefinition};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Settings for the on-disk copy of fetched results.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskCacheConfig {
+    pub enabled: bool,
+    /// Database file; `None` when the platform has no user data directory.
+    pub path: Option<PathBuf>,
+    /// Size cap for the stored responses; the oldest are evicted first.
+    pub max_bytes: u64,
+}
+
+impl Default for DiskCacheConfig {
+    fn default() -> Self {
+        DiskCacheConfig {
+            enabled: true,
+            path: default_path(),
+            max_bytes: 50 * 1024 * 1024,
+        }
+    }
+}
+
+/// `cache.redb` under the user's data directory, e.g.
+/// `~/.local/share/frontend-local-rust-os/` on Linux.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(env!("CARGO_PKG_NAME")).join("cache.redb"))
+}
+
+// Serialized responses by key, plus (stored at in Unix ms, size) per key so
+// listing and eviction don't have to read the responses themselves.
+const RESPONSES: TableDefinition<&str, &[u8]> = TableDefinition::new("responses");
+const METADATA: TableDefinition<&str, (u64, u64)> = TableDefinition::new("metadata");
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum StoredValue {
+    Trip(Box<Trip>),
+    Page(Page<Trip>),
+}
+
+/// Failure reading or writing the cache database.
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub struct DiskCacheError(Box<redb::Error>);
+
+macro_rules! disk_cache_error_from {
+    ($($source:ty),*) => {$(
+        impl From<$source> for DiskCacheError {
+            fn from(e: $source) -> Self {
+                DiskCacheError(Box::new(e.into()))
+            }
+        }
+    )*};
+}
+
+disk_cache_error_from!(
+    redb::Error,
+    redb::DatabaseError,
+    redb::TransactionError,
+    redb::TableError,
+    redb::StorageError,
+    redb::CommitError
+);
+
+/// One stored response, as listed in the UI.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskCacheEntry {
+    pub key: String,
+    pub stored_at: SystemTime,
+    pub size: u64,
+}
+
+/// Fetched trips and result pages kept in an embedded database, so previous
+/// searches can be reopened without connectivity.
+pub struct DiskCache {
+    path: PathBuf,
+    db: Database,
+    max_bytes: u64,
+}
+
+impl DiskCache {
+    /// Opens the database, creating it and its directory if needed. Fails if
+    /// another instance of the app holds it open.
+    pub fn open(path: &Path, max_bytes: u64) -> Result<Self, Box<dyn Error>> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+        }
+        let db = Database::create(path)
+            .map_err(|e| format!("Cannot open disk cache {}: {}", path.display(), e))?;
+
+        // Create the tables up front so read transactions always find them
+        let txn = db.begin_write()?;
+        txn.open_table(RESPONSES)?;
+        txn.open_table(METADATA)?;
+        txn.commit()?;
+
+        Ok(DiskCache {
+            path: path.to_path_buf(),
+            db,
+            max_bytes,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn max_bytes(&self) -> u64 {
+        self.max_bytes
+    }
+
+    /// Stored responses, newest first.
+    pub fn entries(&self) -> Result<Vec<DiskCacheEntry>, DiskCacheError> {
+        let txn = self.db.begin_read()?;
+        let metadata = txn.open_table(METADATA)?;
+        let mut entries = metadata
+            .iter()?
+            .map(|entry| {
+                let (key, value) = entry?;
+                let (stored_at, size) = value.value();
+                Ok(DiskCacheEntry {
+                    key: key.value().to_string(),
+                    stored_at: UNIX_EPOCH + Duration::from_millis(stored_at),
+                    size,
+                })
+            })
+            .collect::<Result<Vec<_>, DiskCacheError>>()?;
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.stored_at));
+        Ok(entries)
+    }
+
+    pub fn remove(&self, key: &str) -> Result<(), DiskCacheError> {
+        let txn = self.db.begin_write()?;
+        txn.open_table(RESPONSES)?.remove(key)?;
+        txn.open_table(METADATA)?.remove(key)?;
+        txn.commit()?;
+        Ok(())
+    }
+
+    pub fn clear(&self) -> Result<(), DiskCacheError> {
+        let txn = self.db.begin_write()?;
+        txn.open_table(RESPONSES)?.retain(|_, _| false)?;
+        txn.open_table(METADATA)?.retain(|_, _| false)?;
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// The stored response and its age. Entries that no longer deserialize,
+    /// e.g. after a format change, count as missing.
+    fn get(&self, key: &CacheKey) -> Result<Option<(Duration, StoredValue)>, DiskCacheError> {
+        let key = key.to_string();
+        let txn = self.db.begin_read()?;
+        let Some(stored_at) = txn.open_table(METADATA)?.get(key.as_str())? else {
+            return Ok(None);
+        };
+        let Some(bytes) = txn.open_table(RESPONSES)?.get(key.as_str())? else {
+            return Ok(None);
+        };
+
+        let age = Duration::from_millis(unix_millis().saturating_sub(stored_at.value().0));
+        Ok(serde_json::from_slice(bytes.value())
+            .ok()
+            .map(|value| (age, value)))
+    }
+
+    /// Stores a response, then evicts the oldest ones until the total size
+    /// fits under the cap.
+    fn put(&self, key: &CacheKey, value: &StoredValue) -> Result<(), DiskCacheError> {
+        let key = key.to_string();
+        let bytes = serde_json::to_vec(value).expect("cached values serialize to JSON");
+
+        let txn = self.db.begin_write()?;
+        {
+            let mut responses = txn.open_table(RESPONSES)?;
+            let mut metadata = txn.open_table(METADATA)?;
+            responses.insert(key.as_str(), bytes.as_slice())?;
+            metadata.insert(key.as_str(), (unix_millis(), bytes.len() as u64))?;
+
+            let mut stored = metadata
+                .iter()?
+                .map(|entry| {
+                    let (key, value) = entry?;
+                    let (stored_at, size) = value.value();
+                    Ok((stored_at, key.value().to_string(), size))
+                })
+                .collect::<Result<Vec<_>, DiskCacheError>>()?;
+            // Oldest first; the response just stored goes last
+            stored.sort_by_key(|(stored_at, stored_key, _)| (*stored_key == key, *stored_at));
+
+            let mut total: u64 = stored.iter().map(|(_, _, size)| size).sum();
+            for (_, key, size) in stored {
+                if total <= self.max_bytes {
+                    break;
+                }
+                responses.remove(key.as_str())?;
+                metadata.remove(key.as_str())?;
+                total -= size;
+            }
+        }
+        txn.commit()?;
+        Ok(())
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// Decorator that writes every successful response to a [`DiskCache`] and
+/// serves the stored copy when the backend can't be reached, including
+/// while a circuit breaker below it is open. A 404 or a 400 is a real answer
+/// and is passed through.
+///
+/// Trips and pages served from disk carry their age in [`Trip::cache_age`]
+/// and [`Page::cache_age`].
+pub struct DiskCachedBackend {
+    inner: SharedBackend,
+    cache: Arc<DiskCache>,
+}
+
+impl DiskCachedBackend {
+    pub fn new(inner: SharedBackend, cache: Arc<DiskCache>) -> Self {
+        DiskCachedBackend { inner, cache }
+    }
+
+    /// Runs a database operation on the blocking pool: redb transactions
+    /// read and fsync the file, which would stall the runtime's workers.
+    async fn on_disk<T, F>(&self, operation: F) -> Result<T, Box<dyn Error + Send + Sync>>
+    where
+        T: Send + 'static,
+        F: FnOnce(&DiskCache) -> Result<T, DiskCacheError> + Send + 'static,
+    {
+        let cache = self.cache.clone();
+        Ok(tokio::task::spawn_blocking(move || operation(&cache)).await??)
+    }
+
+    // Disk problems never fail a query; they only cost the offline copy
+    async fn store(&self, key: &CacheKey, value: StoredValue) {
+        let stored_key = key.clone();
+        let result = self
+            .on_disk(move |cache| cache.put(&stored_key, &value))
+            .await;
+        if let Err(e) = result {
+            tracing::warn!(key = %key, error = %e, "disk cache: cannot store response");
+        }
+    }
+
+    async fn stored(&self, key: &CacheKey) -> Option<(Duration, StoredValue)> {
+        let stored_key = key.clone();
+        self.on_disk(move |cache| cache.get(&stored_key))
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!(key = %key, error = %e, "disk cache: cannot read response");
+                None
+            })
+    }
+
+    async fn page<F>(&self, key: CacheKey, fetch: F) -> Result<Page<Trip>, ApiError>
+    where
+        F: Future<Output = Result<Page<Trip>, ApiError>>,
+    {
+        match fetch.await {
+            Ok(page) => {
+                if page.cache_age.is_none() {
+                    self.store(&key, StoredValue::Page(page.clone())).await;
+                }
+                Ok(page)
+            }
+            Err(e) if e.is_outage() => match self.stored(&key).await {
+                Some((age, StoredValue::Page(mut page))) => {
+                    page.cache_age = Some(age);
+                    Ok(page)
+                }
+                _ => Err(e),
+            },
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[async_trait]
+impl TripBackend for DiskCachedBackend {
+    fn describe(&self) -> String {
+        format!("{} (con copia en disco)", self.inner.describe())
+    }
+
+    fn clear_cache(&self) {
+        // Blocks on the file; the cache panel calls it on the blocking pool
+        if let Err(e) = self.cache.clear() {
+            tracing::warn!(
+                path = %self.cache.path().display(),
+                error = %e,
+                "disk cache: cannot clear"
+            );
+        }
+        self.inner.clear_cache();
+    }
+
+    async fn reauthenticate(&self) -> Result<bool, ApiError> {
+        self.inner.reauthenticate().await
+    }
+
+    async fn is_alive(&self) -> Result<bool, ApiError> {
+        self.inner.is_alive().await
+    }
+
+    async fn get_by_index(&self, index: &str) -> Result<Trip, ApiError> {
+        let key = CacheKey::index(index);
+        match self.inner.get_by_index(index).await {
+            Ok(trip) => {
+                if trip.cache_age.is_none() {
+                    self.store(&key, StoredValue::Trip(Box::new(trip.clone())))
+                        .await;
+                }
+                Ok(trip)
+            }
+            Err(e) if e.is_outage() => match self.stored(&key).await {
+                Some((age, StoredValue::Trip(mut trip))) => {
+                    trip.cache_age = Some(age);
+                    Ok(*trip)
+                }
+                _ => Err(e),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn get_by_price_range(
+        &self,
+        data: &GetByPriceRangeInput,
+    ) -> Result<Page<Trip>, ApiError> {
+        self.page(
+            CacheKey::price_range(data),
+            self.inner.get_by_price_range(data),
+        )
+        .await
+    }
+
+    async fn get_by_destination(
+        &self,
+        data: &GetByDestinationInput,
+    ) -> Result<Page<Trip>, ApiError> {
+        self.page(
+            CacheKey::destination(data),
+            self.inner.get_by_destination(data),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::cache::{CacheConfig, CachedBackend};
+    use crate::api::test_support::{trip, TestBackend};
+    use std::sync::atomic::Ordering;
+
+    #[tokio::test]
+    async fn trip_from_disk_is_marked_and_not_cached_as_fresh() {
+        let path =
+            std::env::temp_dir().join(format!("disk-cache-test-{}.redb", std::process::id()));
+        let disk = Arc::new(DiskCache::open(&path, 1024 * 1024).unwrap());
+        let flaky = Arc::new(TestBackend::new(vec![trip(5, 1, "10"), trip(6, 1, "10")]));
+        let disk_backed = Arc::new(DiskCachedBackend::new(flaky.clone(), disk));
+        // One memory slot, so trip 6 evicts trip 5 from memory but not disk
+        let config = CacheConfig {
+            max_entries: 1,
+            ..CacheConfig::default()
+        };
+        let backend = CachedBackend::new(disk_backed, &config);
+
+        assert_eq!(backend.get_by_index("5").await.unwrap().cache_age, None);
+        backend.get_by_index("6").await.unwrap();
+
+        flaky.down.store(true, Ordering::SeqCst);
+        let stale = backend.get_by_index("5").await.unwrap();
+        assert!(stale.cache_age.is_some());
+        assert_eq!(stale.index.raw(), "5");
+
+        // The stale copy didn't go into the memory cache
+        flaky.down.store(false, Ordering::SeqCst);
+        assert_eq!(backend.get_by_index("5").await.unwrap().cache_age, None);
+
+        drop(backend);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,98 @@
+use thiserror::Error;
+
+/// Errors returned by [`TripApiClient`](super::apicalls::TripApiClient).
+///
+/// Only owned strings are stored so the error can be cloned into UI state.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ApiError {
+    /// The request never got a response: DNS, connection refused, TLS, reset.
+    #[error("transport error: {0}")]
+    Transport(String),
+
+    /// Connecting or reading the response took longer than the configured timeout.
+    #[error("request timed out")]
+    Timeout,
+
+    /// The backend answered 404 for the requested resource.
+    #[error("not found: {0}")]
+    NotFound(String),
+
+    /// Any other non-success status, with the response body for context.
+    #[error("HTTP {status}: {body}")]
+    Http { status: u16, body: String },
+
+    /// The backend rejected the credentials (401 or 403), or no valid token
+    /// could be obtained. Never holds the credentials themselves.
+    #[error("authentication failed: {0}")]
+    Unauthorized(String),
+
+    /// The response arrived but didn't match the expected JSON shape.
+    #[error("invalid response: {0}")]
+    Decode(String),
+
+    /// The circuit breaker is open after repeated failures, so the request
+    /// wasn't sent. A probe goes through in about `retry_in_secs`.
+    #[error("backend unavailable, next attempt in {retry_in_secs}s")]
+    Unavailable { retry_in_secs: u64 },
+
+    /// A replayed request has no recorded response, or a recorded one can't
+    /// be saved. The message lists how the request differs from the closest
+    /// recording.
+    #[error("cassette: {0}")]
+    Cassette(String),
+}
+
+impl ApiError {
+    /// Whether repeating the same request may succeed: transport failures,
+    /// timeouts and the gateway errors a cold-starting backend produces.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ApiError::Transport(_) | ApiError::Timeout => true,
+            ApiError::Http { status, .. } => matches!(status, 502..=504),
+            ApiError::NotFound(_)
+            | ApiError::Unauthorized(_)
+            | ApiError::Decode(_)
+            | ApiError::Unavailable { .. }
+            | ApiError::Cassette(_) => false,
+        }
+    }
+
+    /// True for 5xx responses.
+    pub fn is_server_error(&self) -> bool {
+        matches!(self, ApiError::Http { status, .. } if *status >= 500)
+    }
+
+    /// Whether the backend is unreachable or failing, as opposed to giving a
+    /// real answer such as a 404: transient errors, 5xx responses and an
+    /// open circuit breaker.
+    pub fn is_outage(&self) -> bool {
+        self.is_transient()
+            || self.is_server_error()
+            || matches!(self, ApiError::Unavailable { .. })
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            ApiError::Timeout
+        } else if e.is_decode() {
+            ApiError::Decode(e.to_string())
+        } else {
+            // The causes tell a rejected certificate from a refused proxy
+            let mut message = e.to_string();
+            let mut source = std::error::Error::source(&e);
+            while let Some(cause) = source {
+                message = format!("{}: {}", message, cause);
+                source = cause.source();
+            }
+            ApiError::Transport(message)
+        }
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(e: serde_json::Error) -> Self {
+        ApiError::Decode(e.to_string())
+    }
+}
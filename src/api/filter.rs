@@ -0,0 +1,177 @@
+use super::backend::SharedBackend;
+use super::error::ApiError;
+use super::page::{Page, PageRequest, RejectedItem};
+use super::paging::{page_stream, PageProgress, PageStreamOptions, PagedQuery};
+use super::trip::{PaymentType, Trip};
+use chrono::{Datelike, NaiveDate, Timelike, Weekday};
+use futures::StreamExt;
+use rust_decimal::Decimal;
+use std::time::Duration;
+
+/// Page size used to walk the source query.
+pub const SOURCE_PER_PAGE: u32 = 50;
+
+/// Source trips read before giving up, so a broad query can't download the
+/// whole dataset.
+pub const DEFAULT_SCAN_LIMIT: usize = 2000;
+
+/// Inclusive range; `None` leaves that end open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Range<T> {
+    pub min: Option<T>,
+    pub max: Option<T>,
+}
+
+impl<T: PartialOrd> Range<T> {
+    pub fn is_open(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.min.as_ref().is_none_or(|min| value >= min)
+            && self.max.as_ref().is_none_or(|max| value <= max)
+    }
+
+    /// Whether `value` is in the range; an unknown value only passes an open
+    /// range.
+    fn accepts(&self, value: Option<T>) -> bool {
+        self.is_open() || value.is_some_and(|value| self.contains(&value))
+    }
+}
+
+impl<T> Default for Range<T> {
+    fn default() -> Self {
+        Range {
+            min: None,
+            max: None,
+        }
+    }
+}
+
+/// Predicates applied to trips on the client. Empty lists and open ranges
+/// accept everything; a trip whose column didn't parse fails any predicate
+/// on that column.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TripFilter {
+    pub pickup_date: Range<NaiveDate>,
+    pub dropoff_date: Range<NaiveDate>,
+    /// Days of the week of the pickup.
+    pub pickup_weekdays: Vec<Weekday>,
+    /// Hour of the day of the pickup, 0 to 23.
+    pub pickup_hour: Range<u32>,
+    pub pickup_zones: Vec<u32>,
+    pub payment_types: Vec<PaymentType>,
+    pub passengers: Range<u32>,
+    /// Miles.
+    pub distance: Range<f64>,
+    pub tip: Range<Decimal>,
+    pub total: Range<Decimal>,
+}
+
+impl TripFilter {
+    pub fn matches(&self, trip: &Trip) -> bool {
+        let pickup = trip.tpep_pickup_datetime.get();
+        let dropoff = trip.tpep_dropoff_datetime.get();
+        self.pickup_date.accepts(pickup.map(|at| at.date()))
+            && self.dropoff_date.accepts(dropoff.map(|at| at.date()))
+            && accepts_any(&self.pickup_weekdays, pickup.map(|at| at.weekday()))
+            && self.pickup_hour.accepts(pickup.map(|at| at.hour()))
+            && accepts_any(&self.pickup_zones, trip.pu_location_id.get())
+            && accepts_any(&self.payment_types, trip.payment_type.get())
+            && self.passengers.accepts(trip.passenger_count.get())
+            && self.distance.accepts(trip.trip_distance.get())
+            && self.tip.accepts(trip.tip_amount.get())
+            && self.total.accepts(trip.total_amount.get())
+    }
+}
+
+fn accepts_any<T: PartialEq>(allowed: &[T], value: Option<T>) -> bool {
+    allowed.is_empty() || value.is_some_and(|value| allowed.contains(&value))
+}
+
+/// Trips of a source query that passed a [`TripFilter`], kept in memory to
+/// page through them without asking the backend again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilteredTrips {
+    pub trips: Vec<Trip>,
+    /// Source records read, rejected ones included.
+    pub scanned: usize,
+    /// Trips the backend matched for the source query.
+    pub source_total: u32,
+    /// Backend time across every source page.
+    pub time_ms: u32,
+    /// Age of the oldest source page served from the local cache.
+    pub cache_age: Option<Duration>,
+    /// Source records that couldn't be decoded, so weren't filtered.
+    pub rejected: Vec<RejectedItem>,
+}
+
+impl FilteredTrips {
+    /// Whether the scan limit stopped the walk before the end of the source.
+    pub fn truncated(&self) -> bool {
+        self.scanned < self.source_total as usize
+    }
+
+    pub fn page(&self, paging: PageRequest) -> Page<Trip> {
+        let per_page = paging.per_page.max(1);
+        let total = self.trips.len() as u32;
+        Page {
+            items: self
+                .trips
+                .iter()
+                .skip(paging.offset())
+                .take(per_page as usize)
+                .cloned()
+                .collect(),
+            page: paging.page,
+            pages: total.div_ceil(per_page),
+            per_page,
+            time_ms: self.time_ms,
+            total,
+            cache_age: self.cache_age,
+            rejected: Vec::new(),
+        }
+    }
+}
+
+/// Walks every page of `source` and keeps the trips that match `filter`.
+///
+/// `options.max_items` caps the source trips read; see
+/// [`FilteredTrips::truncated`].
+pub async fn filter_trips(
+    backend: SharedBackend,
+    source: PagedQuery,
+    filter: &TripFilter,
+    options: PageStreamOptions,
+    on_progress: impl FnMut(&PageProgress),
+) -> Result<FilteredTrips, ApiError> {
+    let max_items = options.max_items.unwrap_or(usize::MAX);
+    let mut filtered = FilteredTrips {
+        trips: Vec::new(),
+        scanned: 0,
+        source_total: 0,
+        time_ms: 0,
+        cache_age: None,
+        rejected: Vec::new(),
+    };
+    let mut pages = std::pin::pin!(page_stream(backend, source, options, on_progress));
+    while let Some(page) = pages.next().await {
+        let page = page?;
+        let items = page
+            .items
+            .into_iter()
+            .take(max_items.saturating_sub(filtered.scanned));
+        for trip in items {
+            filtered.scanned += 1;
+            if filter.matches(&trip) {
+                filtered.trips.push(trip);
+            }
+        }
+        filtered.scanned += page.rejected.len();
+        filtered.rejected.extend(page.rejected);
+        filtered.source_total = page.total;
+        filtered.time_ms += page.time_ms;
+        filtered.cache_age = filtered.cache_age.max(page.cache_age);
+    }
+    Ok(filtered)
+}
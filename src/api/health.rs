@@ -0,0 +1,189 @@
+use super::backend::TripBackend;
+use super::error::ApiError;
+use std::time::{Duration, Instant};
+
+/// Settings for the periodic backend health check.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthConfig {
+    pub enabled: bool,
+    /// Time between checks while the backend is up or degraded.
+    pub interval: Duration,
+    /// Time between checks while it is down, to notice the recovery early.
+    pub down_interval: Duration,
+    /// A check that takes longer than this fails with a timeout.
+    pub timeout: Duration,
+    /// Round trips slower than this mark the backend as degraded.
+    pub degraded_latency: Duration,
+    /// Consecutive failed checks before the backend counts as down.
+    pub failures_before_down: u32,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        HealthConfig {
+            enabled: true,
+            interval: Duration::from_secs(30),
+            down_interval: Duration::from_secs(5),
+            timeout: Duration::from_secs(5),
+            degraded_latency: Duration::from_millis(1500),
+            failures_before_down: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthState {
+    /// No check has finished yet.
+    Unknown,
+    Up,
+    /// Answering slowly, or failing for fewer checks than the down threshold.
+    Degraded,
+    Down,
+}
+
+/// Result of the latest checks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthStatus {
+    pub state: HealthState,
+    /// Round trip of the last successful check.
+    pub latency: Option<Duration>,
+    /// Error of the last check, cleared once one succeeds.
+    pub last_error: Option<ApiError>,
+    pub consecutive_failures: u32,
+}
+
+impl Default for HealthStatus {
+    fn default() -> Self {
+        HealthStatus {
+            state: HealthState::Unknown,
+            latency: None,
+            last_error: None,
+            consecutive_failures: 0,
+        }
+    }
+}
+
+/// Tracks the backend state from [`TripBackend::is_alive`] checks.
+#[derive(Debug, Clone)]
+pub struct HealthMonitor {
+    config: HealthConfig,
+    status: HealthStatus,
+}
+
+impl HealthMonitor {
+    pub fn new(config: HealthConfig) -> Self {
+        HealthMonitor {
+            config,
+            status: HealthStatus::default(),
+        }
+    }
+
+    pub fn status(&self) -> &HealthStatus {
+        &self.status
+    }
+
+    /// Pings the backend once, without retries, and updates the state.
+    pub async fn check(&mut self, backend: &dyn TripBackend) -> &HealthStatus {
+        let started = Instant::now();
+        let result = match tokio::time::timeout(self.config.timeout, backend.is_alive()).await {
+            Ok(Ok(_)) => Ok(started.elapsed()),
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err(ApiError::Timeout),
+        };
+        self.record(result);
+        &self.status
+    }
+
+    /// Delay until the next check, shorter while the backend is down.
+    pub fn next_delay(&self) -> Duration {
+        match self.status.state {
+            HealthState::Down => self.config.down_interval,
+            _ => self.config.interval,
+        }
+    }
+
+    fn record(&mut self, result: Result<Duration, ApiError>) {
+        let status = &mut self.status;
+        match result {
+            Ok(latency) => {
+                status.state = if latency > self.config.degraded_latency {
+                    HealthState::Degraded
+                } else {
+                    HealthState::Up
+                };
+                status.latency = Some(latency);
+                status.last_error = None;
+                status.consecutive_failures = 0;
+            }
+            Err(e) => {
+                status.consecutive_failures += 1;
+                status.state = if status.consecutive_failures >= self.config.failures_before_down {
+                    HealthState::Down
+                } else {
+                    HealthState::Degraded
+                };
+                status.last_error = Some(e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_support::TestBackend;
+    use std::sync::atomic::Ordering;
+
+    fn monitor() -> HealthMonitor {
+        HealthMonitor::new(HealthConfig {
+            timeout: Duration::from_millis(100),
+            degraded_latency: Duration::from_millis(20),
+            ..HealthConfig::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn failures_degrade_then_take_the_backend_down() {
+        let mut monitor = monitor();
+        let backend = TestBackend::new(Vec::new());
+        assert_eq!(monitor.status().state, HealthState::Unknown);
+
+        assert_eq!(monitor.check(&backend).await.state, HealthState::Up);
+        assert!(monitor.status().latency.is_some());
+
+        backend.down.store(true, Ordering::SeqCst);
+        let status = monitor.check(&backend).await;
+        assert_eq!(status.state, HealthState::Degraded);
+        assert_eq!(status.consecutive_failures, 1);
+        assert_eq!(status.last_error, Some(ApiError::Timeout));
+        assert_eq!(monitor.next_delay(), HealthConfig::default().interval);
+
+        assert_eq!(monitor.check(&backend).await.state, HealthState::Down);
+        assert_eq!(monitor.next_delay(), HealthConfig::default().down_interval);
+
+        backend.down.store(false, Ordering::SeqCst);
+        let status = monitor.check(&backend).await;
+        assert_eq!(status.state, HealthState::Up);
+        assert_eq!(status.consecutive_failures, 0);
+        assert_eq!(status.last_error, None);
+    }
+
+    #[tokio::test]
+    async fn slow_answers_degrade_and_hung_ones_fail() {
+        let mut monitor = monitor();
+        let mut backend = TestBackend::new(Vec::new());
+
+        backend.delay = Duration::from_millis(40);
+        let status = monitor.check(&backend).await;
+        assert_eq!(status.state, HealthState::Degraded);
+        assert_eq!(status.consecutive_failures, 0);
+
+        backend.delay = Duration::from_millis(300);
+        let status = monitor.check(&backend).await;
+        assert_eq!(status.state, HealthState::Degraded);
+        assert_eq!(status.consecutive_failures, 1);
+        assert_eq!(status.last_error, Some(ApiError::Timeout));
+        // The latency of the last answer is kept
+        assert!(status.latency.is_some());
+    }
+}
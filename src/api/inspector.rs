@@ -0,0 +1,193 @@
+use super::sync::lock;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tokio::sync::watch;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// Name of the span opened around every call to the backend. Its fields are
+/// those of [`CallRecord`].
+pub const API_CALL_SPAN: &str = "api_call";
+
+/// One call to the backend as traced by its `api_call` span.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallRecord {
+    pub id: u64,
+    pub started_at: SystemTime,
+    /// Request path, e.g. `/trip/price`.
+    pub endpoint: String,
+    /// Query string, without the leading `?`.
+    pub params: String,
+    /// HTTP status of the last attempt, if a response arrived.
+    pub status: Option<u16>,
+    /// Body size of the last response.
+    pub bytes: Option<u64>,
+    /// Wall time seen by the client, retries and backoff included.
+    pub client_ms: Option<u64>,
+    /// `time_ms` reported by the backend, for responses that carry it.
+    pub server_ms: Option<u64>,
+    /// Time the last attempt waited for a free slot under the concurrency
+    /// limit.
+    pub queued_ms: Option<u64>,
+    pub attempts: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl CallRecord {
+    fn new(id: u64) -> Self {
+        CallRecord {
+            id,
+            started_at: SystemTime::now(),
+            endpoint: String::new(),
+            params: String::new(),
+            status: None,
+            bytes: None,
+            client_ms: None,
+            server_ms: None,
+            queued_ms: None,
+            attempts: None,
+            error: None,
+        }
+    }
+
+    /// Client time not spent in the backend: network, TLS, queueing and
+    /// retries.
+    pub fn overhead_ms(&self) -> Option<u64> {
+        Some(self.client_ms?.saturating_sub(self.server_ms?))
+    }
+}
+
+impl Visit for CallRecord {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match field.name() {
+            "status" => self.status = u16::try_from(value).ok(),
+            "bytes" => self.bytes = Some(value),
+            "client_ms" => self.client_ms = Some(value),
+            "server_ms" => self.server_ms = Some(value),
+            "queued_ms" => self.queued_ms = Some(value),
+            "attempts" => self.attempts = Some(value),
+            _ => {}
+        }
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        if let Ok(value) = u64::try_from(value) {
+            self.record_u64(field, value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "endpoint" => self.endpoint = value.to_string(),
+            "params" => self.params = value.to_string(),
+            "error" => self.error = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.record_str(field, &format!("{:?}", value));
+    }
+}
+
+/// The latest finished calls. [`NetworkLayer`] fills it and the inspector
+/// panel reads a clone of the same buffer.
+#[derive(Debug, Clone)]
+pub struct NetworkLog {
+    calls: Arc<Mutex<VecDeque<CallRecord>>>,
+    capacity: usize,
+    next_id: Arc<AtomicU64>,
+    changed: Arc<watch::Sender<u64>>,
+}
+
+impl NetworkLog {
+    /// Keeps at most `capacity` calls, dropping the oldest.
+    pub fn new(capacity: usize) -> Self {
+        NetworkLog {
+            calls: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity: capacity.max(1),
+            next_id: Arc::new(AtomicU64::new(1)),
+            changed: Arc::new(watch::channel(0).0),
+        }
+    }
+
+    /// Calls in the order they finished, oldest first.
+    pub fn calls(&self) -> Vec<CallRecord> {
+        lock(&self.calls).iter().cloned().collect()
+    }
+
+    pub fn clear(&self) {
+        lock(&self.calls).clear();
+        self.notify();
+    }
+
+    /// Ticks every time a call is added or the log is cleared.
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.changed.subscribe()
+    }
+
+    fn push(&self, call: CallRecord) {
+        let mut calls = lock(&self.calls);
+        if calls.len() == self.capacity {
+            calls.pop_front();
+        }
+        calls.push_back(call);
+        drop(calls);
+        self.notify();
+    }
+
+    fn notify(&self) {
+        self.changed.send_modify(|version| *version += 1);
+    }
+}
+
+/// Tracing layer that turns every closed `api_call` span into a
+/// [`CallRecord`] in a [`NetworkLog`].
+#[derive(Debug, Clone)]
+pub struct NetworkLayer {
+    log: NetworkLog,
+}
+
+impl NetworkLayer {
+    pub fn new(log: NetworkLog) -> Self {
+        NetworkLayer { log }
+    }
+}
+
+impl<S> Layer<S> for NetworkLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != API_CALL_SPAN {
+            return;
+        }
+        let Some(span) = ctx.span(id) else { return };
+        let mut call = CallRecord::new(self.log.next_id.fetch_add(1, Ordering::Relaxed));
+        attrs.record(&mut call);
+        span.extensions_mut().insert(call);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let mut extensions = span.extensions_mut();
+        if let Some(call) = extensions.get_mut::<CallRecord>() {
+            values.record(call);
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else { return };
+        let call = span.extensions_mut().remove::<CallRecord>();
+        if let Some(call) = call {
+            self.log.push(call);
+        }
+    }
+}
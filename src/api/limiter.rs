@@ -0,0 +1,42 @@
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Requests sent to the backend at once when nothing else is configured.
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 4;
+
+/// Caps how many requests are in flight at once across the whole app.
+/// Requests over the cap wait in a queue and are sent in arrival order.
+/// A clone draws from the same permits, so copies of the client share one
+/// queue.
+#[derive(Debug, Clone)]
+pub struct RequestLimiter {
+    /// `None` when unlimited.
+    permits: Option<Arc<Semaphore>>,
+}
+
+impl RequestLimiter {
+    /// At most `max_concurrent` requests in flight; `0` means no limit.
+    pub fn new(max_concurrent: usize) -> Self {
+        RequestLimiter {
+            permits: (max_concurrent > 0).then(|| Arc::new(Semaphore::new(max_concurrent))),
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Self::new(0)
+    }
+
+    /// Waits for a free slot; the request may be sent while the returned
+    /// permit is alive.
+    pub async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        let permits = self.permits.clone()?;
+        // The semaphore is never closed
+        permits.acquire_owned().await.ok()
+    }
+}
+
+impl Default for RequestLimiter {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONCURRENT_REQUESTS)
+    }
+}
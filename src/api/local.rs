@@ -0,0 +1,339 @@
+use super::apicalls::{GetByDestinationInput, GetByPriceRangeInput};
+use super::backend::TripBackend;
+use super::error::ApiError;
+use super::page::{Page, PageRequest};
+use super::trip::{Field, Trip};
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::de::value::{Error as DeError, MapDeserializer};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+use std::time::Instant;
+
+/// Backend that answers every query in-process from trips held in memory,
+/// e.g. loaded from a NYC TLC yellow-taxi CSV for offline work.
+///
+/// Queries follow the HTTP backend: the price range filters on
+/// `total_amount` (inclusive), the destination on `do_location_id`, and
+/// pages are 1-based.
+#[derive(Debug, Clone)]
+pub struct LocalBackend {
+    source: String,
+    trips: Vec<Trip>,
+    by_index: HashMap<u64, usize>,
+}
+
+impl LocalBackend {
+    pub fn new(source: impl Into<String>, trips: Vec<Trip>) -> Self {
+        let by_index = trips
+            .iter()
+            .enumerate()
+            .filter_map(|(position, trip)| Some((trip.index.get()?, position)))
+            .collect();
+        LocalBackend {
+            source: source.into(),
+            trips,
+            by_index,
+        }
+    }
+
+    /// Loads a CSV with a header row. Both the TLC column names (`VendorID`,
+    /// `PULocationID`, ...) and the backend's snake_case names are accepted;
+    /// extra columns are ignored. Without an `index` column, rows are numbered
+    /// from 0 in file order. Rows without any trip column are skipped.
+    pub fn from_csv(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_path(path)
+            .map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+
+        let headers = reader
+            .headers()
+            .map_err(|e| format!("{}: {}", path.display(), e))?
+            .clone();
+        let mut trips = Vec::new();
+        for (row, record) in reader.records().enumerate() {
+            let record = record.map_err(|e| format!("{}: {}", path.display(), e))?;
+            // Cells go in as text: letting csv guess numbers would turn
+            // "12.50" into "12.5"
+            let cells = headers.iter().zip(record.iter());
+            let mut trip =
+                match <Trip as Deserialize>::deserialize(MapDeserializer::<_, DeError>::new(cells))
+                {
+                    Ok(trip) => trip,
+                    // A row of empty cells, e.g. a trailing `,,,,`, isn't worth
+                    // failing the whole file over
+                    Err(e) => {
+                        tracing::warn!(
+                            path = %path.display(),
+                            row = row + 1,
+                            error = %e,
+                            "skipping CSV row"
+                        );
+                        continue;
+                    }
+                };
+            if trip.index.raw().is_empty() {
+                trip.index = Field::parse(row.to_string());
+            }
+            trips.push(trip);
+        }
+
+        Ok(Self::new(path.display().to_string(), trips))
+    }
+}
+
+#[async_trait]
+impl TripBackend for LocalBackend {
+    fn describe(&self) -> String {
+        format!(
+            "{} (sin conexión, {} viajes)",
+            self.source,
+            self.trips.len()
+        )
+    }
+
+    async fn is_alive(&self) -> Result<bool, ApiError> {
+        Ok(true)
+    }
+
+    async fn get_by_index(&self, index: &str) -> Result<Trip, ApiError> {
+        let key: u64 = parse_param("index", index)?;
+        self.by_index
+            .get(&key)
+            .map(|&position| self.trips[position].clone())
+            .ok_or_else(|| ApiError::NotFound(format!("trip/{}", index)))
+    }
+
+    async fn get_by_price_range(
+        &self,
+        data: &GetByPriceRangeInput,
+    ) -> Result<Page<Trip>, ApiError> {
+        let started = Instant::now();
+        let min: Decimal = parse_param("min", &data.min)?;
+        let max: Decimal = parse_param("max", &data.max)?;
+        let paging = check_paging(data.paging)?;
+
+        let matches = self.trips.iter().filter(|trip| {
+            trip.total_amount
+                .get()
+                .is_some_and(|total| total >= min && total <= max)
+        });
+        Ok(paginate(matches, paging, started))
+    }
+
+    async fn get_by_destination(
+        &self,
+        data: &GetByDestinationInput,
+    ) -> Result<Page<Trip>, ApiError> {
+        let started = Instant::now();
+        let destination: u32 = parse_param("destination", &data.destination)?;
+        let paging = check_paging(data.paging)?;
+
+        let matches = self
+            .trips
+            .iter()
+            .filter(|trip| trip.do_location_id.get() == Some(destination));
+        Ok(paginate(matches, paging, started))
+    }
+}
+
+/// Rejects malformed parameters the way the HTTP backend does, with a 400.
+fn parse_param<T: std::str::FromStr>(name: &str, raw: &str) -> Result<T, ApiError> {
+    raw.trim().parse().map_err(|_| ApiError::Http {
+        status: 400,
+        body: format!("invalid {}: '{}'", name, raw),
+    })
+}
+
+fn check_paging(paging: PageRequest) -> Result<PageRequest, ApiError> {
+    if paging.page == 0 || paging.per_page == 0 {
+        return Err(ApiError::Http {
+            status: 400,
+            body: "page and per_page must be at least 1".to_string(),
+        });
+    }
+    Ok(paging)
+}
+
+fn paginate<'a>(
+    matches: impl Iterator<Item = &'a Trip>,
+    paging: PageRequest,
+    started: Instant,
+) -> Page<Trip> {
+    let matches: Vec<&Trip> = matches.collect();
+    let total = matches.len() as u32;
+    let items = matches
+        .into_iter()
+        .skip(paging.offset())
+        .take(paging.per_page as usize)
+        .cloned()
+        .collect();
+
+    Page {
+        items,
+        page: paging.page,
+        pages: total.div_ceil(paging.per_page),
+        per_page: paging.per_page,
+        time_ms: started.elapsed().as_millis() as u32,
+        total,
+        cache_age: None,
+        rejected: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_csv(name: &str, contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}.csv", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn rows_without_trip_columns_are_skipped() {
+        let path = write_csv(
+            "local-empty-row",
+            "VendorID,DOLocationID,total_amount\n1,142,12.50\n,,\n2,236,8.00\n",
+        );
+        let backend = LocalBackend::from_csv(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let indices: Vec<&str> = backend.trips.iter().map(|trip| trip.index.raw()).collect();
+        assert_eq!(indices, ["0", "2"]);
+        assert_eq!(backend.trips[0].total_amount.raw(), "12.50");
+    }
+
+    #[test]
+    fn both_header_styles_load() {
+        let tlc = write_csv(
+            "local-tlc",
+            "VendorID,PULocationID,DOLocationID,total_amount,extra\n2,161,236,14.30,x\n",
+        );
+        let snake = write_csv(
+            "local-snake",
+            "index,vendor_id,pu_location_id,do_location_id,total_amount\n57,2,161,236,14.30\n",
+        );
+        let tlc_backend = LocalBackend::from_csv(&tlc).unwrap();
+        let snake_backend = LocalBackend::from_csv(&snake).unwrap();
+        let _ = std::fs::remove_file(&tlc);
+        let _ = std::fs::remove_file(&snake);
+
+        for (backend, index) in [(&tlc_backend, "0"), (&snake_backend, "57")] {
+            let trip = &backend.trips[0];
+            assert_eq!(trip.index.raw(), index);
+            assert_eq!(trip.pu_location_id.get(), Some(161));
+            assert_eq!(trip.do_location_id.get(), Some(236));
+            assert_eq!(trip.total_amount.raw(), "14.30");
+        }
+        assert!(
+            LocalBackend::from_csv(Path::new("/nonexistent/trips.csv")).is_err(),
+            "a missing file fails the load"
+        );
+    }
+
+    fn backend() -> LocalBackend {
+        let path = write_csv(
+            "local-paging",
+            "DOLocationID,total_amount\n\
+             236,5.00\n\
+             132,10.00\n\
+             236,15.00\n\
+             236,20.00\n\
+             132,25.00\n",
+        );
+        let backend = LocalBackend::from_csv(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        backend
+    }
+
+    fn indices(page: &Page<Trip>) -> Vec<&str> {
+        page.items.iter().map(|trip| trip.index.raw()).collect()
+    }
+
+    #[tokio::test]
+    async fn queries_filter_and_page() {
+        let backend = backend();
+
+        assert_eq!(
+            backend.get_by_index("3").await.unwrap().total_amount.raw(),
+            "20.00"
+        );
+        assert!(matches!(
+            backend.get_by_index("9").await,
+            Err(ApiError::NotFound(_))
+        ));
+
+        // Both ends of the range are included
+        let price = GetByPriceRangeInput {
+            min: "10".to_string(),
+            max: "20.00".to_string(),
+            paging: PageRequest::new(1, 2),
+        };
+        let page = backend.get_by_price_range(&price).await.unwrap();
+        assert_eq!(indices(&page), ["1", "2"]);
+        assert_eq!((page.page, page.pages, page.total), (1, 2, 3));
+
+        let page = backend
+            .get_by_price_range(&GetByPriceRangeInput {
+                paging: PageRequest::new(2, 2),
+                ..price.clone()
+            })
+            .await
+            .unwrap();
+        assert_eq!(indices(&page), ["3"]);
+
+        let destination = GetByDestinationInput {
+            destination: "236".to_string(),
+            paging: PageRequest::new(1, 10),
+        };
+        let page = backend.get_by_destination(&destination).await.unwrap();
+        assert_eq!(indices(&page), ["0", "2", "3"]);
+        assert_eq!((page.pages, page.total), (1, 3));
+
+        // Past the last page: empty, but the totals still hold
+        let page = backend
+            .get_by_destination(&GetByDestinationInput {
+                paging: PageRequest::new(3, 10),
+                ..destination
+            })
+            .await
+            .unwrap();
+        assert!(page.items.is_empty());
+        assert_eq!(page.total, 3);
+    }
+
+    #[tokio::test]
+    async fn malformed_queries_are_rejected() {
+        let backend = backend();
+        let bad_request = |result: Result<Page<Trip>, ApiError>| {
+            matches!(result, Err(ApiError::Http { status: 400, .. }))
+        };
+
+        assert!(bad_request(
+            backend
+                .get_by_price_range(&GetByPriceRangeInput {
+                    min: "ten".to_string(),
+                    max: "20".to_string(),
+                    paging: PageRequest::first(10),
+                })
+                .await
+        ));
+        assert!(bad_request(
+            backend
+                .get_by_destination(&GetByDestinationInput {
+                    destination: "236".to_string(),
+                    paging: PageRequest::new(0, 10),
+                })
+                .await
+        ));
+        assert!(matches!(
+            backend.get_by_index("abc").await,
+            Err(ApiError::Http { status: 400, .. })
+        ));
+    }
+}
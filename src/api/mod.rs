@@ -1 +1,24 @@
 pub mod apicalls;
+pub mod auth;
+pub mod backend;
+pub mod batch;
+pub mod breaker;
+pub mod cache;
+pub mod cassette;
+pub mod coalesce;
+pub mod destinations;
+pub mod disk_cache;
+pub mod error;
+pub mod filter;
+pub mod health;
+pub mod inspector;
+pub mod limiter;
+pub mod local;
+pub mod page;
+pub mod paging;
+pub mod query;
+pub mod retry;
+mod sync;
+#[cfg(test)]
+mod test_support;
+pub mod trip;
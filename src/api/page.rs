@@ -0,0 +1,291 @@
+use super::trip::Field;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::time::Duration;
+
+/// Which page of a list endpoint to request. Pages are 1-based and sent as
+/// the `page` and `per_page` query parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PageRequest {
+    pub page: u32,
+    pub per_page: u32,
+}
+
+impl PageRequest {
+    pub fn new(page: u32, per_page: u32) -> Self {
+        PageRequest { page, per_page }
+    }
+
+    pub fn first(per_page: u32) -> Self {
+        Self::new(1, per_page)
+    }
+
+    /// The same request for another page.
+    pub fn with_page(self, page: u32) -> Self {
+        PageRequest { page, ..self }
+    }
+
+    /// The previous page, if this isn't the first one.
+    pub fn prev(self) -> Option<Self> {
+        (self.page > 1).then(|| self.with_page(self.page - 1))
+    }
+
+    pub fn next(self) -> Self {
+        self.with_page(self.page.saturating_add(1))
+    }
+
+    /// Number of items before this page across the whole result set.
+    pub fn offset(self) -> usize {
+        (self.page.max(1) as usize - 1).saturating_mul(self.per_page as usize)
+    }
+}
+
+impl Default for PageRequest {
+    fn default() -> Self {
+        Self::first(10)
+    }
+}
+
+/// One page of results from a list endpoint, as returned by the backend.
+///
+/// Decoding is lenient: the counters accept numbers or numeric strings and
+/// fall back to what the items imply when missing, and each item is decoded
+/// on its own, so one bad record lands in `rejected` instead of failing the
+/// whole page.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub page: u32,
+    pub pages: u32,
+    pub per_page: u32,
+    /// Time the backend spent on the query.
+    pub time_ms: u32,
+    /// Matching items across all pages.
+    pub total: u32,
+    /// Age of the response when it was served from the local cache.
+    #[serde(skip)]
+    pub cache_age: Option<Duration>,
+    /// Items of this page that couldn't be decoded, in order.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rejected: Vec<RejectedItem>,
+}
+
+/// A record the backend sent that couldn't be decoded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RejectedItem {
+    /// 0-based position in the whole result set, like [`Page::offset`].
+    pub position: usize,
+    /// The record as received, as compact JSON.
+    pub raw: String,
+    pub error: String,
+}
+
+/// `Page` as sent over the wire, before the items are decoded.
+#[derive(Deserialize)]
+struct RawPage {
+    items: Vec<serde_json::Value>,
+    #[serde(default)]
+    page: Field<u32>,
+    #[serde(default)]
+    pages: Field<u32>,
+    #[serde(default)]
+    per_page: Field<u32>,
+    #[serde(default)]
+    time_ms: Field<u32>,
+    #[serde(default)]
+    total: Field<u32>,
+    /// Records rejected before this page was stored in the disk cache.
+    #[serde(default)]
+    rejected: Vec<RejectedItem>,
+}
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for Page<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawPage::deserialize(deserializer)?;
+        let received = raw.items.len() + raw.rejected.len();
+        let page = raw.page.get().unwrap_or(1).max(1);
+        let per_page = raw.per_page.get().unwrap_or(received as u32).max(1);
+        let offset = PageRequest::new(page, per_page).offset();
+        let total = raw.total.get().unwrap_or((offset + received) as u32);
+
+        let mut items = Vec::with_capacity(raw.items.len());
+        let mut rejected = raw.rejected;
+        // Positions of records already rejected are skipped over
+        let mut positions =
+            (offset..).filter(|position| !rejected.iter().any(|item| item.position == *position));
+        let mut newly_rejected = Vec::new();
+        for value in raw.items {
+            let position = positions.next().unwrap_or(offset);
+            match T::deserialize(&value) {
+                Ok(item) => items.push(item),
+                Err(e) => newly_rejected.push(RejectedItem {
+                    position,
+                    raw: value.to_string(),
+                    error: e.to_string(),
+                }),
+            }
+        }
+        rejected.extend(newly_rejected);
+        rejected.sort_by_key(|item| item.position);
+
+        Ok(Page {
+            items,
+            page,
+            pages: raw.pages.get().unwrap_or(total.div_ceil(per_page)),
+            per_page,
+            time_ms: raw.time_ms.get().unwrap_or(0),
+            total,
+            cache_age: None,
+            rejected,
+        })
+    }
+}
+
+impl<T> Page<T> {
+    /// The request this page answers.
+    pub fn request(&self) -> PageRequest {
+        PageRequest::new(self.page, self.per_page)
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.page < self.pages
+    }
+
+    pub fn has_prev(&self) -> bool {
+        self.page > 1
+    }
+
+    pub fn next_request(&self) -> Option<PageRequest> {
+        self.has_next().then(|| self.request().next())
+    }
+
+    pub fn prev_request(&self) -> Option<PageRequest> {
+        self.request().prev()
+    }
+
+    /// Number of items before this page across the whole result set.
+    pub fn offset(&self) -> usize {
+        self.request().offset()
+    }
+
+    /// 0-based position in the whole result set of each item, skipping
+    /// those of rejected records.
+    pub fn item_positions(&self) -> impl Iterator<Item = usize> + '_ {
+        (self.offset()..)
+            .filter(|position| !self.rejected.iter().any(|item| item.position == *position))
+            .take(self.items.len())
+    }
+
+    /// 1-based positions of the first and last record on this page, rejected
+    /// ones included, or `None` when the page is empty.
+    pub fn item_range(&self) -> Option<(usize, usize)> {
+        let count = self.items.len() + self.rejected.len();
+        if count == 0 {
+            return None;
+        }
+        let first = self.offset() + 1;
+        Some((first, first + count - 1))
+    }
+
+    /// Range shown to the user, e.g. `"11-20 de 78"`.
+    pub fn range_text(&self) -> String {
+        match self.item_range() {
+            Some((first, last)) => format!("{}-{} de {}", first, last, self.total),
+            None => format!("0 de {}", self.total),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(page: u32, pages: u32, items: usize) -> Page<u32> {
+        Page {
+            items: (0..items as u32).collect(),
+            page,
+            pages,
+            per_page: 10,
+            time_ms: 0,
+            total: pages * 10,
+            cache_age: None,
+            rejected: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn navigation_stops_at_both_ends() {
+        let first = sample(1, 3, 10);
+        assert!(first.has_next());
+        assert!(!first.has_prev());
+        assert_eq!(first.next_request(), Some(PageRequest::new(2, 10)));
+        assert_eq!(first.prev_request(), None);
+
+        let last = sample(3, 3, 10);
+        assert!(!last.has_next());
+        assert!(last.has_prev());
+        assert_eq!(last.next_request(), None);
+        assert_eq!(last.prev_request(), Some(PageRequest::new(2, 10)));
+
+        assert!(!sample(1, 0, 0).has_next());
+        assert_eq!(PageRequest::new(0, 10).offset(), 0);
+        assert_eq!(PageRequest::new(3, 10).offset(), 20);
+    }
+
+    #[test]
+    fn positions_skip_rejected_records() {
+        let mut page = sample(2, 3, 3);
+        page.rejected = vec![RejectedItem {
+            position: 11,
+            raw: "{}".to_string(),
+            error: "boom".to_string(),
+        }];
+        assert_eq!(page.offset(), 10);
+        assert_eq!(page.item_positions().collect::<Vec<_>>(), [10, 12, 13]);
+        assert_eq!(page.item_range(), Some((11, 14)));
+        assert_eq!(page.range_text(), "11-14 de 30");
+
+        let empty = Page::<u32> {
+            total: 0,
+            ..sample(1, 0, 0)
+        };
+        assert_eq!(empty.item_range(), None);
+        assert_eq!(empty.range_text(), "0 de 0");
+    }
+
+    #[test]
+    fn missing_counters_fall_back_to_the_items() {
+        let page: Page<u32> =
+            serde_json::from_str(r#"{"items": [1, 2, 3], "page": "2", "per_page": 3}"#).unwrap();
+        assert_eq!(page.items, [1, 2, 3]);
+        assert_eq!((page.page, page.per_page), (2, 3));
+        // Three before this page and three on it
+        assert_eq!((page.total, page.pages), (6, 2));
+        assert_eq!(page.time_ms, 0);
+
+        let page: Page<u32> = serde_json::from_str(r#"{"items": [1, 2]}"#).unwrap();
+        assert_eq!(
+            (page.page, page.per_page, page.total, page.pages),
+            (1, 2, 2, 1)
+        );
+    }
+
+    #[test]
+    fn items_that_are_not_the_right_type_are_rejected() {
+        let page: Page<u32> = serde_json::from_str(
+            r#"{"items": [1, "two", [3], 4], "page": 2, "per_page": 4, "total": 8}"#,
+        )
+        .unwrap();
+        assert_eq!(page.items, [1, 4]);
+        let rejected: Vec<(usize, &str)> = page
+            .rejected
+            .iter()
+            .map(|item| (item.position, item.raw.as_str()))
+            .collect();
+        assert_eq!(rejected, [(5, r#""two""#), (6, "[3]")]);
+        assert_eq!(page.item_positions().collect::<Vec<_>>(), [4, 7]);
+
+        // Items that aren't a list fail the whole page
+        serde_json::from_str::<Page<u32>>(r#"{"items": {"a": 1}}"#).unwrap_err();
+    }
+}
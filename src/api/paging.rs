@@ -0,0 +1,259 @@
+use super::apicalls::{GetByDestinationInput, GetByPriceRangeInput};
+use super::backend::SharedBackend;
+use super::destinations::{get_by_destinations, GetByDestinationsInput, DEFAULT_CONCURRENCY};
+use super::error::ApiError;
+use super::page::Page;
+use super::trip::Trip;
+use futures::future;
+use futures::stream::{self, Stream, StreamExt};
+
+/// A paginated query that can be walked page by page.
+#[derive(Debug, Clone)]
+pub enum PagedQuery {
+    PriceRange(GetByPriceRangeInput),
+    Destination(GetByDestinationInput),
+    Destinations(GetByDestinationsInput),
+}
+
+impl PagedQuery {
+    fn with_page(&self, page: u32) -> Self {
+        let mut query = self.clone();
+        match &mut query {
+            PagedQuery::PriceRange(input) => input.paging = input.paging.with_page(page),
+            PagedQuery::Destination(input) => input.paging = input.paging.with_page(page),
+            PagedQuery::Destinations(input) => input.paging = input.paging.with_page(page),
+        }
+        query
+    }
+
+    async fn fetch(self, backend: SharedBackend) -> Result<Page<Trip>, ApiError> {
+        match &self {
+            PagedQuery::PriceRange(input) => backend.get_by_price_range(input).await,
+            PagedQuery::Destination(input) => backend.get_by_destination(input).await,
+            PagedQuery::Destinations(input) => {
+                get_by_destinations(backend.as_ref(), input, DEFAULT_CONCURRENCY).await
+            }
+        }
+    }
+}
+
+/// How a [`page_stream`] walks the result pages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageStreamOptions {
+    /// Pages requested at the same time after the first one.
+    pub concurrency: usize,
+    /// Stop after this many trips; no page beyond the one that reaches the
+    /// cap is requested.
+    pub max_items: Option<usize>,
+}
+
+impl Default for PageStreamOptions {
+    fn default() -> Self {
+        PageStreamOptions {
+            concurrency: 4,
+            max_items: None,
+        }
+    }
+}
+
+/// Reported after every page received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageProgress {
+    pub pages_fetched: u32,
+    /// Pages the walk will request, counting the item cap.
+    pub pages_total: u32,
+    pub items_fetched: usize,
+    /// Matching trips on the backend, regardless of the item cap.
+    pub items_total: u32,
+}
+
+/// Walks every page of `query`, starting at its `page`, and yields them in
+/// order.
+///
+/// The first page is fetched alone to learn `pages`; the rest are requested
+/// with up to `options.concurrency` in flight. The stream ends after the
+/// first error.
+pub fn page_stream(
+    backend: SharedBackend,
+    query: PagedQuery,
+    options: PageStreamOptions,
+    mut on_progress: impl FnMut(&PageProgress),
+) -> impl Stream<Item = Result<Page<Trip>, ApiError>> {
+    let concurrency = options.concurrency.max(1);
+    let max_items = options.max_items;
+    let first = query.clone().fetch(backend.clone());
+
+    let pages = stream::once(first).flat_map(move |first| {
+        let rest = match &first {
+            Ok(page) => {
+                let last = last_page(page, max_items);
+                let backend = backend.clone();
+                let query = query.clone();
+                stream::iter(page.page + 1..=last)
+                    .map(move |number| query.with_page(number).fetch(backend.clone()))
+                    .buffered(concurrency)
+                    .boxed()
+            }
+            Err(_) => stream::empty().boxed(),
+        };
+        stream::once(future::ready(first)).chain(rest)
+    });
+
+    let mut progress = None::<PageProgress>;
+    pages
+        .scan(false, |failed, result| {
+            if *failed {
+                return future::ready(None);
+            }
+            *failed = result.is_err();
+            future::ready(Some(result))
+        })
+        .inspect(move |result| {
+            let Ok(page) = result else { return };
+            let progress = progress.get_or_insert(PageProgress {
+                pages_fetched: 0,
+                pages_total: last_page(page, max_items).saturating_sub(page.page) + 1,
+                items_fetched: 0,
+                items_total: page.total,
+            });
+            progress.pages_fetched += 1;
+            progress.items_fetched += page.items.len();
+            on_progress(progress);
+        })
+}
+
+/// Like [`page_stream`], one trip at a time, stopping at `options.max_items`.
+pub fn trip_stream(
+    backend: SharedBackend,
+    query: PagedQuery,
+    options: PageStreamOptions,
+    on_progress: impl FnMut(&PageProgress),
+) -> impl Stream<Item = Result<Trip, ApiError>> {
+    let max_items = options.max_items.unwrap_or(usize::MAX);
+    page_stream(backend, query, options, on_progress)
+        .flat_map(|result| {
+            stream::iter(match result {
+                Ok(page) => page.items.into_iter().map(Ok).collect(),
+                Err(e) => vec![Err(e)],
+            })
+        })
+        .take(max_items)
+}
+
+/// Last page worth requesting given the first one received and the item cap.
+fn last_page(first: &Page<Trip>, max_items: Option<usize>) -> u32 {
+    let Some(max_items) = max_items else {
+        return first.pages;
+    };
+    let per_page = first.per_page.max(1) as usize;
+    let needed = max_items.div_ceil(per_page).max(1) as u32;
+    first.pages.min(first.page.saturating_add(needed - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::page::PageRequest;
+    use crate::api::test_support::{trip, TestBackend};
+    use futures::TryStreamExt;
+    use std::sync::Arc;
+
+    /// Seven matching trips, two per page.
+    fn backend() -> Arc<TestBackend> {
+        let trips = (1..=7).map(|index| trip(index, 1, "10")).collect();
+        Arc::new(TestBackend::new(trips))
+    }
+
+    fn price_range(min: &str) -> PagedQuery {
+        PagedQuery::PriceRange(GetByPriceRangeInput {
+            min: min.to_string(),
+            max: "15".to_string(),
+            paging: PageRequest::first(2),
+        })
+    }
+
+    fn options(max_items: Option<usize>) -> PageStreamOptions {
+        PageStreamOptions {
+            max_items,
+            ..PageStreamOptions::default()
+        }
+    }
+
+    #[test]
+    fn item_cap_limits_the_last_page() {
+        let first = |page| Page {
+            items: Vec::new(),
+            page,
+            pages: 4,
+            per_page: 2,
+            time_ms: 0,
+            total: 7,
+            cache_age: None,
+            rejected: Vec::new(),
+        };
+        assert_eq!(last_page(&first(1), None), 4);
+        assert_eq!(last_page(&first(1), Some(3)), 2);
+        assert_eq!(last_page(&first(1), Some(4)), 2);
+        assert_eq!(last_page(&first(1), Some(0)), 1);
+        assert_eq!(last_page(&first(3), Some(100)), 4);
+        assert_eq!(last_page(&first(3), Some(1)), 3);
+    }
+
+    #[tokio::test]
+    async fn walks_every_page_in_order() {
+        let source = backend();
+        let mut progress = Vec::new();
+        let pages: Vec<Page<Trip>> =
+            page_stream(source.clone(), price_range("5"), options(None), |p| {
+                progress.push(*p)
+            })
+            .try_collect()
+            .await
+            .unwrap();
+
+        let numbers: Vec<u32> = pages.iter().map(|page| page.page).collect();
+        assert_eq!(numbers, [1, 2, 3, 4]);
+        assert_eq!(source.started(), 4);
+        assert_eq!(
+            progress.last(),
+            Some(&PageProgress {
+                pages_fetched: 4,
+                pages_total: 4,
+                items_fetched: 7,
+                items_total: 7,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn max_items_stops_early() {
+        let source = backend();
+        let mut progress = None;
+        let trips: Vec<Trip> =
+            trip_stream(source.clone(), price_range("5"), options(Some(3)), |p| {
+                progress = Some(*p)
+            })
+            .try_collect()
+            .await
+            .unwrap();
+
+        let indices: Vec<&str> = trips.iter().map(|trip| trip.index.raw()).collect();
+        assert_eq!(indices, ["1", "2", "3"]);
+        assert_eq!(source.started(), 2);
+        let progress = progress.unwrap();
+        assert_eq!((progress.pages_total, progress.items_total), (2, 7));
+    }
+
+    #[tokio::test]
+    async fn stream_ends_after_an_error() {
+        let source = backend();
+        let results: Vec<_> = page_stream(source.clone(), price_range("x"), options(None), |_| {})
+            .collect()
+            .await;
+        assert_eq!(results.len(), 1);
+        assert!(matches!(
+            results[0],
+            Err(ApiError::Http { status: 400, .. })
+        ));
+    }
+}
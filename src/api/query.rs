@@ -0,0 +1,549 @@
+use super::apicalls::GetByPriceRangeInput;
+use super::destinations::GetByDestinationsInput;
+use super::filter::{Range, TripFilter, SOURCE_PER_PAGE};
+use super::page::PageRequest;
+use super::paging::PagedQuery;
+use super::trip::PaymentType;
+use chrono::{NaiveDate, Weekday};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+/// Page sizes the forms offer and accept.
+pub const PER_PAGE_OPTIONS: [u32; 4] = [5, 10, 20, 50];
+
+/// Most destinations in one query, as many as there are taxi zones.
+pub const MAX_DESTINATIONS: usize = 265;
+
+/// Most trips in one batch lookup by index.
+pub const MAX_BATCH_INDICES: usize = 1000;
+
+/// Why the text of a form field can't be used in a query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum FieldError {
+    #[error("required")]
+    Empty,
+    #[error("not a number")]
+    NotANumber,
+    #[error("not a whole number")]
+    NotAnInteger,
+    #[error("must not be negative")]
+    Negative,
+    #[error("too large")]
+    TooLarge,
+    #[error("must be at least 1")]
+    BelowOne,
+    #[error("must be one of {PER_PAGE_OPTIONS:?}")]
+    PageSize,
+    #[error("minimum is greater than maximum")]
+    MinAboveMax,
+    #[error("range start is greater than its end")]
+    ReversedRange,
+    #[error("more than {MAX_DESTINATIONS} destinations")]
+    TooManyDestinations,
+    #[error("more than {MAX_BATCH_INDICES} indices")]
+    TooManyIndices,
+    #[error("not a date (YYYY-MM-DD)")]
+    NotADate,
+    #[error("must be an hour from 0 to 23")]
+    NotAnHour,
+    #[error("destinations or a price range are required")]
+    NoSource,
+}
+
+/// Raw text of the page and page size fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PagingForm {
+    pub page: String,
+    pub per_page: String,
+}
+
+impl PagingForm {
+    /// Fields filled in with an existing request, e.g. after paging through
+    /// the results.
+    pub fn from_request(request: PageRequest) -> Self {
+        PagingForm {
+            page: request.page.to_string(),
+            per_page: request.per_page.to_string(),
+        }
+    }
+
+    pub fn parse(&self) -> Result<PageRequest, PagingErrors> {
+        let page = parse_page(&self.page);
+        let per_page = parse_per_page(&self.per_page);
+        match (page, per_page) {
+            (Ok(page), Ok(per_page)) => Ok(PageRequest::new(page, per_page)),
+            (page, per_page) => Err(PagingErrors {
+                page: page.err(),
+                per_page: per_page.err(),
+            }),
+        }
+    }
+}
+
+impl Default for PagingForm {
+    fn default() -> Self {
+        Self::from_request(PageRequest::default())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PagingErrors {
+    pub page: Option<FieldError>,
+    pub per_page: Option<FieldError>,
+}
+
+/// Field errors of a price range query; `None` for the fields that are fine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PriceRangeErrors {
+    pub min: Option<FieldError>,
+    /// Also set when the range is empty, since `max` is usually the field
+    /// being typed in.
+    pub max: Option<FieldError>,
+    pub paging: PagingErrors,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DestinationErrors {
+    pub destination: Option<FieldError>,
+    pub paging: PagingErrors,
+}
+
+/// Builds a price range query from form text, checking every field so all
+/// the errors can be shown at once.
+pub fn price_range_query(
+    min: &str,
+    max: &str,
+    paging: &PagingForm,
+) -> Result<GetByPriceRangeInput, PriceRangeErrors> {
+    let min = parse_price(min);
+    let max = match (&min, parse_price(max)) {
+        (Ok(min), Ok(max)) if *min > max => Err(FieldError::MinAboveMax),
+        (_, max) => max,
+    };
+    match (min, max, paging.parse()) {
+        (Ok(min), Ok(max), Ok(paging)) => Ok(GetByPriceRangeInput {
+            min: min.to_string(),
+            max: max.to_string(),
+            paging,
+        }),
+        (min, max, paging) => Err(PriceRangeErrors {
+            min: min.err(),
+            max: max.err(),
+            paging: paging.err().unwrap_or_default(),
+        }),
+    }
+}
+
+/// Builds a destination query from form text, with one or more taxi zone
+/// ids as accepted by [`parse_destinations`].
+pub fn destination_query(
+    destinations: &str,
+    paging: &PagingForm,
+) -> Result<GetByDestinationsInput, DestinationErrors> {
+    match (parse_destinations(destinations), paging.parse()) {
+        (Ok(destinations), Ok(paging)) => Ok(GetByDestinationsInput {
+            destinations: destinations.iter().map(u32::to_string).collect(),
+            paging,
+        }),
+        (destination, paging) => Err(DestinationErrors {
+            destination: destination.err(),
+            paging: paging.err().unwrap_or_default(),
+        }),
+    }
+}
+
+/// Days of the week a filter keeps pickups from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DaysForm {
+    #[default]
+    Any,
+    Workdays,
+    Weekend,
+}
+
+impl DaysForm {
+    fn weekdays(self) -> Vec<Weekday> {
+        use Weekday::*;
+        match self {
+            DaysForm::Any => Vec::new(),
+            DaysForm::Workdays => vec![Mon, Tue, Wed, Thu, Fri],
+            DaysForm::Weekend => vec![Sat, Sun],
+        }
+    }
+}
+
+/// Raw input of the client-side filter form. Every text field may be left
+/// empty, except that the source query needs destinations or both prices.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FilterForm {
+    pub destinations: String,
+    pub min_price: String,
+    pub max_price: String,
+    pub pickup_from: String,
+    pub pickup_to: String,
+    pub dropoff_from: String,
+    pub dropoff_to: String,
+    pub days: DaysForm,
+    pub hour_from: String,
+    pub hour_to: String,
+    pub pickup_zones: String,
+    pub payment: Option<PaymentType>,
+    pub min_passengers: String,
+    pub max_passengers: String,
+    pub min_distance: String,
+    pub max_distance: String,
+    pub min_tip: String,
+    pub max_tip: String,
+}
+
+/// Field errors of a [`FilterForm`], one per text field.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FilterErrors {
+    pub destinations: Option<FieldError>,
+    pub min_price: Option<FieldError>,
+    pub max_price: Option<FieldError>,
+    pub pickup_from: Option<FieldError>,
+    pub pickup_to: Option<FieldError>,
+    pub dropoff_from: Option<FieldError>,
+    pub dropoff_to: Option<FieldError>,
+    pub hour_from: Option<FieldError>,
+    pub hour_to: Option<FieldError>,
+    pub pickup_zones: Option<FieldError>,
+    pub min_passengers: Option<FieldError>,
+    pub max_passengers: Option<FieldError>,
+    pub min_distance: Option<FieldError>,
+    pub max_distance: Option<FieldError>,
+    pub min_tip: Option<FieldError>,
+    pub max_tip: Option<FieldError>,
+}
+
+impl FilterForm {
+    /// The backend query to walk and the filter for its trips.
+    ///
+    /// With destinations, those are the source and the price range is
+    /// checked on the client, since the backend can't combine both.
+    /// Otherwise the price range is the source.
+    pub fn parse(&self) -> Result<(PagedQuery, TripFilter), FilterErrors> {
+        let mut errors = FilterErrors::default();
+        let destinations = check(
+            &mut errors.destinations,
+            optional(&self.destinations, parse_destinations),
+        );
+        let price = range(
+            (&mut errors.min_price, &mut errors.max_price),
+            (&self.min_price, &self.max_price),
+            parse_price,
+        );
+        let filter = TripFilter {
+            pickup_date: range(
+                (&mut errors.pickup_from, &mut errors.pickup_to),
+                (&self.pickup_from, &self.pickup_to),
+                parse_date,
+            ),
+            dropoff_date: range(
+                (&mut errors.dropoff_from, &mut errors.dropoff_to),
+                (&self.dropoff_from, &self.dropoff_to),
+                parse_date,
+            ),
+            pickup_weekdays: self.days.weekdays(),
+            pickup_hour: range(
+                (&mut errors.hour_from, &mut errors.hour_to),
+                (&self.hour_from, &self.hour_to),
+                parse_hour,
+            ),
+            pickup_zones: check(
+                &mut errors.pickup_zones,
+                optional(&self.pickup_zones, parse_destinations),
+            )
+            .unwrap_or_default(),
+            payment_types: self.payment.into_iter().collect(),
+            passengers: range(
+                (&mut errors.min_passengers, &mut errors.max_passengers),
+                (&self.min_passengers, &self.max_passengers),
+                parse_whole,
+            ),
+            distance: range(
+                (&mut errors.min_distance, &mut errors.max_distance),
+                (&self.min_distance, &self.max_distance),
+                parse_distance,
+            ),
+            tip: range(
+                (&mut errors.min_tip, &mut errors.max_tip),
+                (&self.min_tip, &self.max_tip),
+                parse_price,
+            ),
+            total: Range::default(),
+        };
+
+        let paging = PageRequest::first(SOURCE_PER_PAGE);
+        let source = match (destinations, price) {
+            (Some(destinations), price) => Some((
+                PagedQuery::Destinations(GetByDestinationsInput {
+                    destinations: destinations.iter().map(u32::to_string).collect(),
+                    paging,
+                }),
+                price,
+            )),
+            (
+                None,
+                Range {
+                    min: Some(min),
+                    max: Some(max),
+                },
+            ) => Some((
+                PagedQuery::PriceRange(GetByPriceRangeInput {
+                    min: min.to_string(),
+                    max: max.to_string(),
+                    paging,
+                }),
+                Range::default(),
+            )),
+            (None, _) => {
+                if errors.destinations.is_none() {
+                    errors.destinations = Some(FieldError::NoSource);
+                }
+                None
+            }
+        };
+
+        match source {
+            Some((source, total)) if errors == FilterErrors::default() => {
+                Ok((source, TripFilter { total, ..filter }))
+            }
+            _ => Err(errors),
+        }
+    }
+}
+
+/// Records the error of a field and carries on with a placeholder value, so
+/// every field gets checked.
+fn check<T: Default>(error: &mut Option<FieldError>, result: Result<T, FieldError>) -> T {
+    result.unwrap_or_else(|e| {
+        *error = Some(e);
+        T::default()
+    })
+}
+
+fn optional<T>(
+    raw: &str,
+    parse: impl Fn(&str) -> Result<T, FieldError>,
+) -> Result<Option<T>, FieldError> {
+    match raw.trim() {
+        "" => Ok(None),
+        raw => parse(raw).map(Some),
+    }
+}
+
+/// Parses the two optional ends of a range; an empty range is reported on
+/// the max field.
+fn range<T: PartialOrd>(
+    errors: (&mut Option<FieldError>, &mut Option<FieldError>),
+    (min, max): (&str, &str),
+    parse: impl Fn(&str) -> Result<T, FieldError>,
+) -> Range<T> {
+    let min = optional(min, &parse);
+    let max = match (&min, optional(max, &parse)) {
+        (Ok(Some(min)), Ok(Some(max))) if *min > max => Err(FieldError::MinAboveMax),
+        (_, max) => max,
+    };
+    Range {
+        min: check(errors.0, min),
+        max: check(errors.1, max),
+    }
+}
+
+/// Parses comma-separated zone ids and inclusive ranges, e.g.
+/// `"1, 132, 138"` or `"100-120, 236"`. Repeated ids are kept once, in their
+/// first position.
+pub fn parse_destinations(raw: &str) -> Result<Vec<u32>, FieldError> {
+    parse_id_list(raw, MAX_DESTINATIONS, FieldError::TooManyDestinations)
+}
+
+/// Parses trip indices for a batch lookup, in the same format as
+/// [`parse_destinations`], e.g. `"100-250, 900, 1024"`.
+pub fn parse_indices(raw: &str) -> Result<Vec<u32>, FieldError> {
+    parse_id_list(raw, MAX_BATCH_INDICES, FieldError::TooManyIndices)
+}
+
+/// Ids in `raw`, failing with `too_many` past `limit`.
+fn parse_id_list(raw: &str, limit: usize, too_many: FieldError) -> Result<Vec<u32>, FieldError> {
+    let mut ids = Vec::new();
+    for part in non_empty(raw)?.split(',') {
+        let part = part.trim();
+        // A leading '-' is a sign, not a range
+        let range = part.char_indices().skip(1).find(|&(_, c)| c == '-');
+        let (first, last) = match range {
+            Some((at, _)) => {
+                let first = parse_whole(&part[..at])?;
+                let last = parse_whole(&part[at + 1..])?;
+                if first > last {
+                    return Err(FieldError::ReversedRange);
+                }
+                (first, last)
+            }
+            None => {
+                let id = parse_whole(part)?;
+                (id, id)
+            }
+        };
+        if (last - first) as usize >= limit {
+            return Err(too_many);
+        }
+        for id in first..=last {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        if ids.len() > limit {
+            return Err(too_many);
+        }
+    }
+    Ok(ids)
+}
+
+pub fn parse_price(raw: &str) -> Result<Decimal, FieldError> {
+    let price: Decimal = non_empty(raw)?
+        .parse()
+        .map_err(|_| FieldError::NotANumber)?;
+    if price.is_sign_negative() && !price.is_zero() {
+        return Err(FieldError::Negative);
+    }
+    Ok(price)
+}
+
+fn parse_distance(raw: &str) -> Result<f64, FieldError> {
+    let distance = parse_price(raw)?;
+    distance.to_f64().ok_or(FieldError::TooLarge)
+}
+
+fn parse_date(raw: &str) -> Result<NaiveDate, FieldError> {
+    NaiveDate::parse_from_str(non_empty(raw)?, "%Y-%m-%d").map_err(|_| FieldError::NotADate)
+}
+
+fn parse_hour(raw: &str) -> Result<u32, FieldError> {
+    match parse_whole(raw)? {
+        hour @ 0..=23 => Ok(hour),
+        _ => Err(FieldError::NotAnHour),
+    }
+}
+
+pub fn parse_page(raw: &str) -> Result<u32, FieldError> {
+    match parse_whole(raw)? {
+        0 => Err(FieldError::BelowOne),
+        page => Ok(page),
+    }
+}
+
+pub fn parse_per_page(raw: &str) -> Result<u32, FieldError> {
+    let per_page = parse_whole(raw)?;
+    if !PER_PAGE_OPTIONS.contains(&per_page) {
+        return Err(FieldError::PageSize);
+    }
+    Ok(per_page)
+}
+
+fn parse_whole(raw: &str) -> Result<u32, FieldError> {
+    let raw = non_empty(raw)?;
+    raw.parse().map_err(|_| match raw.parse::<Decimal>() {
+        Ok(n) if n.is_sign_negative() && !n.is_zero() => FieldError::Negative,
+        Ok(n) if n.fract().is_zero() => FieldError::TooLarge,
+        Ok(_) => FieldError::NotAnInteger,
+        Err(_) => FieldError::NotANumber,
+    })
+}
+
+fn non_empty(raw: &str) -> Result<&str, FieldError> {
+    match raw.trim() {
+        "" => Err(FieldError::Empty),
+        raw => Ok(raw),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paging(page: &str, per_page: &str) -> PagingForm {
+        PagingForm {
+            page: page.to_string(),
+            per_page: per_page.to_string(),
+        }
+    }
+
+    #[test]
+    fn price_range_checks_every_field() {
+        let input = price_range_query(" 10 ", "20.50", &PagingForm::default()).unwrap();
+        assert_eq!((input.min.as_str(), input.max.as_str()), ("10", "20.50"));
+        assert_eq!(input.paging, PageRequest::default());
+        price_range_query("15", "15", &PagingForm::default()).unwrap();
+
+        let errors = price_range_query("30", "20", &PagingForm::default()).unwrap_err();
+        assert_eq!(errors.min, None);
+        assert_eq!(errors.max, Some(FieldError::MinAboveMax));
+
+        let errors = price_range_query("-1", "abc", &paging("0", "7")).unwrap_err();
+        assert_eq!(
+            errors,
+            PriceRangeErrors {
+                min: Some(FieldError::Negative),
+                max: Some(FieldError::NotANumber),
+                paging: PagingErrors {
+                    page: Some(FieldError::BelowOne),
+                    per_page: Some(FieldError::PageSize),
+                },
+            }
+        );
+        assert_eq!(parse_price("-0"), Ok(Decimal::ZERO));
+        assert_eq!(parse_price("  "), Err(FieldError::Empty));
+    }
+
+    #[test]
+    fn paging_needs_a_page_from_one_and_an_offered_size() {
+        assert_eq!(paging("3", "20").parse(), Ok(PageRequest::new(3, 20)));
+        for per_page in PER_PAGE_OPTIONS {
+            assert_eq!(parse_per_page(&per_page.to_string()), Ok(per_page));
+        }
+        assert_eq!(parse_per_page("15"), Err(FieldError::PageSize));
+        assert_eq!(parse_page("0"), Err(FieldError::BelowOne));
+        assert_eq!(parse_page("1.5"), Err(FieldError::NotAnInteger));
+        assert_eq!(parse_page("-2"), Err(FieldError::Negative));
+        assert_eq!(parse_page("99999999999"), Err(FieldError::TooLarge));
+        assert_eq!(parse_page(""), Err(FieldError::Empty));
+    }
+
+    #[test]
+    fn id_lists_expand_ranges_and_drop_repeats() {
+        assert_eq!(
+            parse_destinations("132, 138-140 ,132, 139"),
+            Ok(vec![132, 138, 139, 140])
+        );
+        assert_eq!(parse_destinations("5-5"), Ok(vec![5]));
+        assert_eq!(parse_destinations("10-2"), Err(FieldError::ReversedRange));
+        assert_eq!(parse_destinations("-3"), Err(FieldError::Negative));
+        assert_eq!(parse_destinations("1,,2"), Err(FieldError::Empty));
+        assert_eq!(parse_destinations("1, x"), Err(FieldError::NotANumber));
+    }
+
+    #[test]
+    fn id_lists_stop_at_their_limit() {
+        assert_eq!(parse_indices("1-1000").map(|ids| ids.len()), Ok(1000));
+        assert_eq!(parse_indices("1-1001"), Err(FieldError::TooManyIndices));
+        // Repeats don't count towards the limit
+        assert_eq!(
+            parse_indices("1-1000, 500, 1-10").map(|ids| ids.len()),
+            Ok(1000)
+        );
+        assert_eq!(
+            parse_indices("1-1000, 2000"),
+            Err(FieldError::TooManyIndices)
+        );
+
+        let zones = format!("1-{}", MAX_DESTINATIONS);
+        assert_eq!(parse_destinations(&zones).map(|ids| ids.len()), Ok(265));
+        assert_eq!(
+            parse_destinations(&format!("{}, 300", zones)),
+            Err(FieldError::TooManyDestinations)
+        );
+    }
+}
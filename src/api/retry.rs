@@ -0,0 +1,249 @@
+use super::error::ApiError;
+use std::future::Future;
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Exponential backoff settings for idempotent requests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one; `1` disables retries.
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    /// Randomizes each delay between half and the full backoff so clients
+    /// that failed together don't retry in lockstep.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+            multiplier: 2.0,
+            jitter: true,
+        }
+    }
+}
+
+/// Emitted right before a retry is scheduled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryEvent {
+    /// 1 for the first retry, 2 for the second, ...
+    pub retry: u32,
+    pub max_retries: u32,
+    pub delay: Duration,
+    pub error: ApiError,
+}
+
+pub type RetryObserver = Rc<dyn Fn(&RetryEvent)>;
+
+tokio::task_local! {
+    static RETRY_OBSERVER: RetryObserver;
+    static RETRY_FORWARD: broadcast::Sender<RetryEvent>;
+}
+
+/// Runs `future`, reporting every retry scheduled while it's polled to
+/// `observer`. The observer travels with the future rather than the backend,
+/// so callers can watch a single request on any [`TripBackend`].
+///
+/// [`TripBackend`]: super::backend::TripBackend
+pub async fn observe_retries<F: Future>(
+    observer: impl Fn(&RetryEvent) + 'static,
+    future: F,
+) -> F::Output {
+    RETRY_OBSERVER.scope(Rc::new(observer), future).await
+}
+
+/// Like [`observe_retries`] for a future spawned on its own task, which the
+/// callers' observers can't follow: its retries are sent on `events` for
+/// whoever waits on the task to pass on with [`report_retry`].
+pub(crate) async fn forward_retries<F: Future>(
+    events: broadcast::Sender<RetryEvent>,
+    future: F,
+) -> F::Output {
+    RETRY_FORWARD.scope(events, future).await
+}
+
+/// Reports `event` to the observer of the current task, if any.
+pub(crate) fn report_retry(event: &RetryEvent) {
+    let _ = RETRY_OBSERVER.try_with(|observer| observer(event));
+    let _ = RETRY_FORWARD.try_with(|events| events.send(event.clone()));
+}
+
+impl RetryPolicy {
+    /// Delay before the given retry (1-based).
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(30) as i32;
+        let base = self.initial_backoff.as_secs_f64() * self.multiplier.max(1.0).powi(exponent);
+        let capped = base.min(self.max_backoff.as_secs_f64());
+        let delay = if self.jitter {
+            capped * (0.5 + fastrand::f64() * 0.5)
+        } else {
+            capped
+        };
+        Duration::from_secs_f64(delay)
+    }
+
+    /// Runs `op` until it succeeds, fails with a non-transient error, or the
+    /// attempts run out. The last error is returned.
+    pub async fn run<T, F, Fut>(&self, mut op: F) -> Result<T, ApiError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, ApiError>>,
+    {
+        let max_retries = self.max_attempts.max(1) - 1;
+        let mut retry = 0;
+        loop {
+            match op().await {
+                Err(error) if error.is_transient() && retry < max_retries => {
+                    retry += 1;
+                    let delay = self.backoff(retry);
+                    let event = RetryEvent {
+                        retry,
+                        max_retries,
+                        delay,
+                        error,
+                    };
+                    report_retry(&event);
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+            multiplier: 2.0,
+            jitter: false,
+        }
+    }
+
+    #[test]
+    fn backoff_grows_up_to_the_cap() {
+        let policy = RetryPolicy {
+            jitter: false,
+            ..RetryPolicy::default()
+        };
+        let delays: Vec<u64> = (1..=6)
+            .map(|retry| policy.backoff(retry).as_millis() as u64)
+            .collect();
+        assert_eq!(delays, [500, 1000, 2000, 4000, 8000, 8000]);
+        assert_eq!(policy.backoff(u32::MAX), policy.max_backoff);
+
+        // A multiplier below 1 doesn't shrink the delays
+        let flat = RetryPolicy {
+            multiplier: 0.5,
+            ..policy
+        };
+        assert_eq!(flat.backoff(3), flat.initial_backoff);
+    }
+
+    #[test]
+    fn jitter_stays_between_half_and_the_full_backoff() {
+        let policy = RetryPolicy::default();
+        for retry in 1..=6 {
+            let full = RetryPolicy {
+                jitter: false,
+                ..policy.clone()
+            }
+            .backoff(retry);
+            for _ in 0..50 {
+                let delay = policy.backoff(retry);
+                assert!(
+                    delay >= full / 2 && delay <= full,
+                    "{:?} of {:?}",
+                    delay,
+                    full
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_transient_errors_until_the_attempts_run_out() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let attempts = Cell::new(0);
+        let seen = events.clone();
+        let result: Result<(), _> = observe_retries(
+            move |event: &RetryEvent| seen.borrow_mut().push(event.clone()),
+            policy(3).run(|| {
+                attempts.set(attempts.get() + 1);
+                async { Err(ApiError::Timeout) }
+            }),
+        )
+        .await;
+
+        assert_eq!(result, Err(ApiError::Timeout));
+        assert_eq!(attempts.get(), 3);
+        let events = events.borrow();
+        let retries: Vec<(u32, u32, Duration)> = events
+            .iter()
+            .map(|event| (event.retry, event.max_retries, event.delay))
+            .collect();
+        assert_eq!(
+            retries,
+            [
+                (1, 2, Duration::from_millis(1)),
+                (2, 2, Duration::from_millis(2)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn stops_at_success_or_a_lasting_error() {
+        let attempts = Cell::new(0);
+        let result = policy(5)
+            .run(|| {
+                attempts.set(attempts.get() + 1);
+                let attempt = attempts.get();
+                async move {
+                    match attempt {
+                        1 => Err(ApiError::Http {
+                            status: 503,
+                            body: String::new(),
+                        }),
+                        _ => Ok(attempt),
+                    }
+                }
+            })
+            .await;
+        assert_eq!(result, Ok(2));
+
+        let attempts = Cell::new(0);
+        let result: Result<(), _> = policy(5)
+            .run(|| {
+                attempts.set(attempts.get() + 1);
+                async { Err(ApiError::NotFound("5".to_string())) }
+            })
+            .await;
+        assert_eq!(result, Err(ApiError::NotFound("5".to_string())));
+        assert_eq!(attempts.get(), 1);
+
+        // No retries at all with a single attempt, or none configured
+        for max_attempts in [0, 1] {
+            let attempts = Cell::new(0);
+            let result: Result<(), _> = policy(max_attempts)
+                .run(|| {
+                    attempts.set(attempts.get() + 1);
+                    async { Err(ApiError::Timeout) }
+                })
+                .await;
+            result.unwrap_err();
+            assert_eq!(attempts.get(), 1);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use super::apicalls::{GetByDestinationInput, GetByPriceRangeInput};
+use super::backend::TripBackend;
+use super::error::ApiError;
+use super::local::LocalBackend;
+use super::page::{Page, RejectedItem};
+use super::retry::RetryPolicy;
+use super::trip::{Field, Trip};
+use async_trait::async_trait;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// In-memory backend for the unit tests of the decorators. Answers from
+/// `trips` like [`LocalBackend`], and can be slowed down, taken down, or made
+/// to fail a few attempts first.
+pub(crate) struct TestBackend {
+    pub trips: LocalBackend,
+    /// Time every attempt takes.
+    pub delay: Duration,
+    /// Attempts time out while set.
+    pub down: AtomicBool,
+    /// Attempts that fail with a 503 before the next one goes through.
+    pub failures: AtomicU32,
+    /// Applied to every lookup, like the HTTP client does.
+    pub retry: RetryPolicy,
+    /// Destinations answered with a 404.
+    pub unknown_destinations: Vec<String>,
+    /// Trips that list pages send as unreadable records, by index.
+    pub unreadable: Vec<u64>,
+    /// Lookups started and finished, retries not counted.
+    pub started: AtomicUsize,
+    pub finished: AtomicUsize,
+}
+
+impl TestBackend {
+    pub fn new(trips: Vec<Trip>) -> Self {
+        TestBackend {
+            trips: LocalBackend::new("test", trips),
+            delay: Duration::ZERO,
+            down: AtomicBool::new(false),
+            failures: AtomicU32::new(0),
+            retry: RetryPolicy {
+                max_attempts: 1,
+                ..RetryPolicy::default()
+            },
+            unknown_destinations: Vec::new(),
+            unreadable: Vec::new(),
+            started: AtomicUsize::new(0),
+            finished: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn started(&self) -> usize {
+        self.started.load(Ordering::SeqCst)
+    }
+
+    pub fn finished(&self) -> usize {
+        self.finished.load(Ordering::SeqCst)
+    }
+
+    async fn lookup<T, F, Fut>(&self, answer: F) -> Result<T, ApiError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, ApiError>>,
+    {
+        self.started.fetch_add(1, Ordering::SeqCst);
+        let result = self
+            .retry
+            .run(|| async {
+                tokio::time::sleep(self.delay).await;
+                if self.down.load(Ordering::SeqCst) {
+                    return Err(ApiError::Timeout);
+                }
+                let failing = self
+                    .failures
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                    .is_ok();
+                if failing {
+                    return Err(ApiError::Http {
+                        status: 503,
+                        body: "warming up".to_string(),
+                    });
+                }
+                answer().await
+            })
+            .await;
+        self.finished.fetch_add(1, Ordering::SeqCst);
+        result
+    }
+
+    /// Moves the `unreadable` trips of `page` to its rejected records.
+    fn reject_unreadable(&self, mut page: Page<Trip>) -> Page<Trip> {
+        let items = std::mem::take(&mut page.items);
+        for (position, trip) in (page.offset()..).zip(items) {
+            if trip
+                .index
+                .get()
+                .is_some_and(|index| self.unreadable.contains(&index))
+            {
+                page.rejected.push(RejectedItem {
+                    position,
+                    raw: format!("{{\"index\":\"{}\"}}", trip.index),
+                    error: "unreadable".to_string(),
+                });
+            } else {
+                page.items.push(trip);
+            }
+        }
+        page
+    }
+}
+
+#[async_trait]
+impl TripBackend for TestBackend {
+    fn describe(&self) -> String {
+        "test".to_string()
+    }
+
+    async fn is_alive(&self) -> Result<bool, ApiError> {
+        self.lookup(|| async { Ok(true) }).await
+    }
+
+    async fn get_by_index(&self, index: &str) -> Result<Trip, ApiError> {
+        self.lookup(|| self.trips.get_by_index(index)).await
+    }
+
+    async fn get_by_price_range(
+        &self,
+        data: &GetByPriceRangeInput,
+    ) -> Result<Page<Trip>, ApiError> {
+        self.lookup(|| self.trips.get_by_price_range(data))
+            .await
+            .map(|page| self.reject_unreadable(page))
+    }
+
+    async fn get_by_destination(
+        &self,
+        data: &GetByDestinationInput,
+    ) -> Result<Page<Trip>, ApiError> {
+        self.lookup(|| async {
+            if self.unknown_destinations.contains(&data.destination) {
+                return Err(ApiError::NotFound(format!(
+                    "trip/destination/{}",
+                    data.destination
+                )));
+            }
+            self.trips.get_by_destination(data).await
+        })
+        .await
+        .map(|page| self.reject_unreadable(page))
+    }
+}
+
+/// A trip with just the columns the queries look at.
+pub(crate) fn trip(index: u64, destination: u32, total: &str) -> Trip {
+    Trip {
+        index: Field::parse(index.to_string()),
+        do_location_id: Field::parse(destination.to_string()),
+        total_amount: Field::parse(total),
+        ..Trip::default()
+    }
+}
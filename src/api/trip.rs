@@ -0,0 +1,440 @@
+use chrono::{DateTime, NaiveDateTime, TimeDelta};
+use rust_decimal::Decimal;
+use serde::de::value::MapAccessDeserializer;
+use serde::de::{self, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+/// One row of the NYC TLC yellow-taxi dataset as served by the backend.
+///
+/// The backend sends every column as a string. Each column is kept as a
+/// [`Field`], which stores the original text for lossless display next to the
+/// typed value parsed from it. The aliases accept the column names used in the
+/// TLC CSV exports.
+///
+/// Missing and `null` columns are left empty and unknown ones are ignored, so
+/// a change in the backend's schema degrades single columns instead of the
+/// whole trip. A record with none of the columns, like an error object in
+/// place of a trip, is rejected.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(remote = "Self", default)]
+pub struct Trip {
+    #[serde(alias = "VendorID")]
+    pub vendor_id: Field<VendorId>,
+    pub tpep_pickup_datetime: Field<NaiveDateTime>,
+    pub tpep_dropoff_datetime: Field<NaiveDateTime>,
+    pub passenger_count: Field<u32>,
+    pub trip_distance: Field<f64>,
+    #[serde(alias = "RatecodeID")]
+    pub ratecode_id: Field<RateCode>,
+    pub store_and_fwd_flag: Field<StoreAndForward>,
+    #[serde(alias = "PULocationID")]
+    pub pu_location_id: Field<u32>,
+    #[serde(alias = "DOLocationID")]
+    pub do_location_id: Field<u32>,
+    pub payment_type: Field<PaymentType>,
+    pub fare_amount: Field<Decimal>,
+    pub extra: Field<Decimal>,
+    pub mta_tax: Field<Decimal>,
+    pub tip_amount: Field<Decimal>,
+    pub tolls_amount: Field<Decimal>,
+    pub improvement_surcharge: Field<Decimal>,
+    pub total_amount: Field<Decimal>,
+    pub congestion_surcharge: Field<Decimal>,
+    /// Row number in the dataset; absent from the raw TLC files.
+    pub index: Field<u64>,
+    /// Age of the trip when it was served from the local cache.
+    #[serde(skip)]
+    pub cache_age: Option<Duration>,
+}
+
+impl Trip {
+    /// Time between pickup and dropoff, when both timestamps parsed.
+    pub fn duration(&self) -> Option<TimeDelta> {
+        Some(*self.tpep_dropoff_datetime.value()? - *self.tpep_pickup_datetime.value()?)
+    }
+}
+
+impl Serialize for Trip {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Trip::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Trip {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // The derived decoder, generated as an inherent function by
+        // `remote = "Self"`; callers outside go through this trait impl
+        let trip = Trip::deserialize(deserializer)?;
+        if trip == Trip::default() {
+            return Err(de::Error::custom("no trip columns in the record"));
+        }
+        Ok(trip)
+    }
+}
+
+/// A column value that remembers the exact text it was parsed from.
+///
+/// Parsing never fails deserialization: a value that doesn't parse keeps its
+/// raw text and yields `None` from [`Field::value`]. Strings, numbers and
+/// booleans are all accepted, numbers keeping their JSON text; `null` gives an
+/// empty field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field<T> {
+    raw: String,
+    value: Option<T>,
+}
+
+impl<T: FieldValue> Field<T> {
+    pub fn parse(raw: impl Into<String>) -> Self {
+        let raw = raw.into();
+        let value = T::parse_field(raw.trim());
+        Field { raw, value }
+    }
+}
+
+impl<T> Field<T> {
+    /// The text as received from the backend.
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    pub fn value(&self) -> Option<&T> {
+        self.value.as_ref()
+    }
+}
+
+impl<T: Copy> Field<T> {
+    pub fn get(&self) -> Option<T> {
+        self.value
+    }
+}
+
+impl<T> Default for Field<T> {
+    fn default() -> Self {
+        Field {
+            raw: String::new(),
+            value: None,
+        }
+    }
+}
+
+impl<T> fmt::Display for Field<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+impl<T> Serialize for Field<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.raw)
+    }
+}
+
+impl<'de, T: FieldValue> Deserialize<'de> for Field<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(FieldVisitor(PhantomData))
+    }
+}
+
+struct FieldVisitor<T>(PhantomData<T>);
+
+impl<'de, T: FieldValue> Visitor<'de> for FieldVisitor<T> {
+    type Value = Field<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a string, number, boolean or null")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        Ok(Field::parse(value))
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<Self::Value, E> {
+        Ok(Field::parse(value))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        Ok(Field::parse(value.to_string()))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        Ok(Field::parse(value.to_string()))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+        Ok(Field::parse(value.to_string()))
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Self::Value, E> {
+        Ok(Field::parse(value.to_string()))
+    }
+
+    // JSON numbers arrive as a map holding their source text, so "12.50"
+    // isn't shortened to "12.5"
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        let number = serde_json::Number::deserialize(MapAccessDeserializer::new(map))?;
+        Ok(Field::parse(number.to_string()))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Field::default())
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Field::default())
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        Field::deserialize(deserializer)
+    }
+}
+
+/// Parsing from the backend's text encoding; `None` means "not understood".
+pub trait FieldValue: Sized {
+    fn parse_field(raw: &str) -> Option<Self>;
+}
+
+impl FieldValue for u32 {
+    // Counts sometimes come as "1.0" in the TLC exports
+    fn parse_field(raw: &str) -> Option<Self> {
+        raw.parse().ok().or_else(|| {
+            let float: f64 = raw.parse().ok()?;
+            (float.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(&float))
+                .then_some(float as u32)
+        })
+    }
+}
+
+impl FieldValue for u64 {
+    fn parse_field(raw: &str) -> Option<Self> {
+        raw.parse().ok()
+    }
+}
+
+impl FieldValue for f64 {
+    fn parse_field(raw: &str) -> Option<Self> {
+        raw.parse().ok().filter(|v: &f64| v.is_finite())
+    }
+}
+
+impl FieldValue for Decimal {
+    fn parse_field(raw: &str) -> Option<Self> {
+        raw.parse()
+            .ok()
+            .or_else(|| Decimal::from_scientific(raw).ok())
+    }
+}
+
+impl FieldValue for NaiveDateTime {
+    // TLC timestamps are NYC local time without an offset
+    fn parse_field(raw: &str) -> Option<Self> {
+        const FORMATS: [&str; 2] = ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"];
+        FORMATS
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(raw, format).ok())
+            .or_else(|| {
+                DateTime::parse_from_rfc3339(raw)
+                    .ok()
+                    .map(|dt| dt.naive_local())
+            })
+    }
+}
+
+/// Parses integer codes, tolerating the "1.0" float encoding.
+fn parse_code(raw: &str) -> Option<u32> {
+    u32::parse_field(raw)
+}
+
+/// TPEP provider that recorded the trip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VendorId {
+    CreativeMobile,
+    Curb,
+    Myle,
+    Helix,
+    Other(u32),
+}
+
+impl VendorId {
+    pub fn from_code(code: u32) -> Self {
+        match code {
+            1 => VendorId::CreativeMobile,
+            2 => VendorId::Curb,
+            6 => VendorId::Myle,
+            7 => VendorId::Helix,
+            other => VendorId::Other(other),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            VendorId::CreativeMobile => "Creative Mobile Technologies",
+            VendorId::Curb => "Curb Mobility (VeriFone)",
+            VendorId::Myle => "Myle Technologies",
+            VendorId::Helix => "Helix",
+            VendorId::Other(_) => "Proveedor desconocido",
+        }
+    }
+}
+
+impl FieldValue for VendorId {
+    fn parse_field(raw: &str) -> Option<Self> {
+        parse_code(raw).map(VendorId::from_code)
+    }
+}
+
+/// Final rate code in effect at the end of the trip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateCode {
+    Standard,
+    Jfk,
+    Newark,
+    NassauWestchester,
+    Negotiated,
+    GroupRide,
+    Unknown,
+    Other(u32),
+}
+
+impl RateCode {
+    pub fn from_code(code: u32) -> Self {
+        match code {
+            1 => RateCode::Standard,
+            2 => RateCode::Jfk,
+            3 => RateCode::Newark,
+            4 => RateCode::NassauWestchester,
+            5 => RateCode::Negotiated,
+            6 => RateCode::GroupRide,
+            99 => RateCode::Unknown,
+            other => RateCode::Other(other),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RateCode::Standard => "Tarifa estándar",
+            RateCode::Jfk => "JFK",
+            RateCode::Newark => "Newark",
+            RateCode::NassauWestchester => "Nassau o Westchester",
+            RateCode::Negotiated => "Tarifa negociada",
+            RateCode::GroupRide => "Viaje compartido",
+            RateCode::Unknown | RateCode::Other(_) => "Desconocida",
+        }
+    }
+}
+
+impl FieldValue for RateCode {
+    fn parse_field(raw: &str) -> Option<Self> {
+        parse_code(raw).map(RateCode::from_code)
+    }
+}
+
+/// Whether the record was held in the vehicle before reaching the vendor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StoreAndForward {
+    StoreAndForward,
+    NotStored,
+}
+
+impl FieldValue for StoreAndForward {
+    fn parse_field(raw: &str) -> Option<Self> {
+        match raw {
+            "Y" | "y" => Some(StoreAndForward::StoreAndForward),
+            "N" | "n" => Some(StoreAndForward::NotStored),
+            _ => None,
+        }
+    }
+}
+
+/// How the passenger paid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PaymentType {
+    FlexFare,
+    CreditCard,
+    Cash,
+    NoCharge,
+    Dispute,
+    Unknown,
+    Voided,
+    Other(u32),
+}
+
+impl PaymentType {
+    pub fn from_code(code: u32) -> Self {
+        match code {
+            0 => PaymentType::FlexFare,
+            1 => PaymentType::CreditCard,
+            2 => PaymentType::Cash,
+            3 => PaymentType::NoCharge,
+            4 => PaymentType::Dispute,
+            5 => PaymentType::Unknown,
+            6 => PaymentType::Voided,
+            other => PaymentType::Other(other),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PaymentType::FlexFare => "Flex Fare",
+            PaymentType::CreditCard => "Tarjeta de crédito",
+            PaymentType::Cash => "Efectivo",
+            PaymentType::NoCharge => "Sin cargo",
+            PaymentType::Dispute => "En disputa",
+            PaymentType::Unknown | PaymentType::Other(_) => "Desconocido",
+            PaymentType::Voided => "Viaje anulado",
+        }
+    }
+}
+
+impl FieldValue for PaymentType {
+    fn parse_field(raw: &str) -> Option<Self> {
+        parse_code(raw).map(PaymentType::from_code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::page::Page;
+
+    #[test]
+    fn numbers_keep_their_source_text() {
+        let trip: Trip =
+            serde_json::from_str(r#"{"index": 7, "fare_amount": 12.50, "trip_distance": 1e2}"#)
+                .unwrap();
+        assert_eq!(trip.fare_amount.raw(), "12.50");
+        assert_eq!(trip.fare_amount.get(), Some(Decimal::new(1250, 2)));
+        assert_eq!(trip.trip_distance.raw(), "1e2");
+        assert_eq!(trip.index.get(), Some(7));
+
+        // Through serde_json::Value too, as page items are decoded
+        let value: serde_json::Value = serde_json::from_str(r#"{"total_amount": 30.10}"#).unwrap();
+        let trip = <Trip as Deserialize>::deserialize(&value).unwrap();
+        assert_eq!(trip.total_amount.raw(), "30.10");
+    }
+
+    #[test]
+    fn record_without_trip_columns_is_rejected() {
+        let error = serde_json::from_str::<Trip>(r#"{"error": "boom"}"#).unwrap_err();
+        assert!(error.to_string().contains("no trip columns"), "{}", error);
+        serde_json::from_str::<Trip>("{}").unwrap_err();
+
+        let page: Page<Trip> =
+            serde_json::from_str(r#"{"items": [{"index": "1"}, {"error": "boom"}]}"#).unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.rejected.len(), 1);
+        assert_eq!(page.rejected[0].position, 1);
+        assert_eq!(page.rejected[0].raw, r#"{"error":"boom"}"#);
+    }
+
+    #[test]
+    fn serializes_the_source_text() {
+        let trip: Trip = serde_json::from_str(r#"{"index": "3", "tip_amount": 2.00}"#).unwrap();
+        let json = serde_json::to_value(&trip).unwrap();
+        assert_eq!(json["tip_amount"], "2.00");
+        assert_eq!(serde_json::from_value::<Trip>(json).unwrap(), trip);
+    }
+}
@@ -0,0 +1,279 @@
+//! Local stand-in for the trip backend.
+//!
+//! Serves `/`, `/trip/{index}`, `/trip/price` and `/trip/destination/{id}`
+//! from a fixture CSV with the same JSON shapes as the Cloud Run backend, so
+//! the desktop app and the tests can run without the network:
+//!
+//! ```bash
+//! cargo run --features mock-server --bin mock-server -- --port 8080 --data fixtures/trips.csv
+//! cargo run -- --api-url http://127.0.0.1:8080/
+//! ```
+//!
+//! `--cold-start <n>` answers the first `n` requests with a 503, like a
+//! backend that is still starting, to exercise the client's retries.
+//!
+//! `--tls-cert <pem> --tls-key <pem>` serves HTTPS with that certificate
+//! and PKCS#8 key, to try custom root certificates against it. Plain HTTP
+//! requests in absolute form are answered too, so the mock also stands in
+//! for an HTTP proxy.
+//!
+//! `--token <token>` answers 401 to any request without
+//! `Authorization: Bearer <token>` or `X-API-Key: <token>`, like a deployment
+//! behind auth.
+
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use frontend_local_rust_os::api::apicalls::{
+    GetByDestinationInput, GetByPriceRangeInput, PageRequest,
+};
+use frontend_local_rust_os::api::backend::TripBackend;
+use frontend_local_rust_os::api::error::ApiError;
+use frontend_local_rust_os::api::local::LocalBackend;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use hyper_util::service::TowerToHyperService;
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::path::{Path as FilePath, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio_native_tls::native_tls::{Identity, TlsAcceptor};
+
+const DEFAULT_DATA: &str = "fixtures/trips.csv";
+const DEFAULT_PORT: u16 = 8080;
+
+struct AppState {
+    backend: LocalBackend,
+    cold_start_left: AtomicU32,
+    token: Option<String>,
+}
+
+// Flat on purpose: `#[serde(flatten)]` can't parse numbers from a query string
+#[derive(Debug, Deserialize)]
+struct PriceQuery {
+    min: String,
+    max: String,
+    page: u32,
+    per_page: u32,
+}
+
+#[tokio::main]
+async fn main() {
+    let mut data = PathBuf::from(DEFAULT_DATA);
+    let mut port = DEFAULT_PORT;
+    let mut cold_start = 0;
+    let mut token = None;
+    let mut tls_cert = None;
+    let mut tls_key = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next();
+        match (arg.as_str(), value) {
+            ("--data", Some(value)) => data = PathBuf::from(value),
+            ("--port", Some(value)) => port = value.parse().unwrap_or_else(|_| usage()),
+            ("--cold-start", Some(value)) => cold_start = value.parse().unwrap_or_else(|_| usage()),
+            ("--token", Some(value)) => token = Some(value),
+            ("--tls-cert", Some(value)) => tls_cert = Some(PathBuf::from(value)),
+            ("--tls-key", Some(value)) => tls_key = Some(PathBuf::from(value)),
+            _ => usage(),
+        }
+    }
+
+    let tls = match (tls_cert, tls_key) {
+        (Some(cert), Some(key)) => match tls_acceptor(&cert, &key) {
+            Ok(acceptor) => Some(acceptor),
+            Err(e) => {
+                eprintln!("Cannot load TLS certificate: {}", e);
+                std::process::exit(2);
+            }
+        },
+        (None, None) => None,
+        _ => usage(),
+    };
+
+    let backend = match LocalBackend::from_csv(&data) {
+        Ok(backend) => backend,
+        Err(e) => {
+            eprintln!("Cannot load fixtures: {}", e);
+            std::process::exit(2);
+        }
+    };
+    println!("Serving {}", backend.describe());
+
+    let state = Arc::new(AppState {
+        backend,
+        cold_start_left: AtomicU32::new(cold_start),
+        token,
+    });
+    let app = Router::new()
+        .route("/", get(root))
+        .route("/trip/price", get(by_price))
+        .route("/trip/destination/:destination", get(by_destination))
+        .route("/trip/:index", get(by_index))
+        .layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .with_state(state);
+
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Cannot bind {}: {}", addr, e);
+            std::process::exit(2);
+        }
+    };
+    // The real address, since `--port 0` lets the OS pick a free port
+    let addr = listener.local_addr().unwrap_or(addr);
+    if let Some(tls) = tls {
+        println!("Mock backend listening on https://{}/", addr);
+        serve_tls(listener, tls, app).await;
+        return;
+    }
+    println!("Mock backend listening on http://{}/", addr);
+    if let Err(e) = axum::serve(listener, app).await {
+        eprintln!("Server error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn tls_acceptor(
+    cert: &FilePath,
+    key: &FilePath,
+) -> Result<TlsAcceptor, Box<dyn std::error::Error>> {
+    let identity = Identity::from_pkcs8(&std::fs::read(cert)?, &std::fs::read(key)?)?;
+    Ok(TlsAcceptor::new(identity)?)
+}
+
+/// `axum::serve` only speaks plain HTTP, so TLS connections are accepted and
+/// handed to hyper one by one.
+async fn serve_tls(listener: TcpListener, tls: TlsAcceptor, app: Router) {
+    let tls = tokio_native_tls::TlsAcceptor::from(tls);
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                eprintln!("Accept error: {}", e);
+                continue;
+            }
+        };
+        let tls = tls.clone();
+        let service = TowerToHyperService::new(app.clone());
+        tokio::spawn(async move {
+            let stream = match tls.accept(stream).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("TLS handshake with {} failed: {}", peer, e);
+                    return;
+                }
+            };
+            let connection = auto::Builder::new(TokioExecutor::new())
+                .serve_connection(TokioIo::new(stream), service)
+                .await;
+            if let Err(e) = connection {
+                eprintln!("Connection with {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: mock-server [--data <csv>] [--port <port>] [--cold-start <requests>] [--token <token>] [--tls-cert <pem> --tls-key <pem>]"
+    );
+    std::process::exit(2);
+}
+
+/// Rejects requests without the configured token, if any.
+async fn require_token(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(token) = &state.token else {
+        return next.run(request).await;
+    };
+    let headers = request.headers();
+    let bearer = format!("Bearer {}", token);
+    let authorized = headers
+        .get(header::AUTHORIZATION)
+        .is_some_and(|value| value == bearer.as_str())
+        || headers
+            .get("x-api-key")
+            .is_some_and(|value| value == token.as_str());
+    if !authorized {
+        return (StatusCode::UNAUTHORIZED, "Missing or invalid credentials").into_response();
+    }
+    next.run(request).await
+}
+
+async fn root(State(state): State<Arc<AppState>>) -> Response {
+    if let Some(response) = cold_start(&state) {
+        return response;
+    }
+    "Server is alive".into_response()
+}
+
+async fn by_index(State(state): State<Arc<AppState>>, Path(index): Path<String>) -> Response {
+    if let Some(response) = cold_start(&state) {
+        return response;
+    }
+    respond(state.backend.get_by_index(&index).await)
+}
+
+async fn by_price(State(state): State<Arc<AppState>>, Query(query): Query<PriceQuery>) -> Response {
+    if let Some(response) = cold_start(&state) {
+        return response;
+    }
+    let input = GetByPriceRangeInput {
+        min: query.min,
+        max: query.max,
+        paging: PageRequest::new(query.page, query.per_page),
+    };
+    respond(state.backend.get_by_price_range(&input).await)
+}
+
+async fn by_destination(
+    State(state): State<Arc<AppState>>,
+    Path(destination): Path<String>,
+    Query(paging): Query<PageRequest>,
+) -> Response {
+    if let Some(response) = cold_start(&state) {
+        return response;
+    }
+    let input = GetByDestinationInput {
+        destination,
+        paging,
+    };
+    respond(state.backend.get_by_destination(&input).await)
+}
+
+/// Consumes one simulated cold-start failure, if any are left.
+fn cold_start(state: &AppState) -> Option<Response> {
+    state
+        .cold_start_left
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+            left.checked_sub(1)
+        })
+        .ok()
+        .map(|_| (StatusCode::SERVICE_UNAVAILABLE, "Service starting").into_response())
+}
+
+fn respond<T: serde::Serialize>(result: Result<T, ApiError>) -> Response {
+    match result {
+        Ok(body) => Json(body).into_response(),
+        Err(ApiError::NotFound(path)) => {
+            (StatusCode::NOT_FOUND, format!("Not found: {}", path)).into_response()
+        }
+        Err(ApiError::Http { status, body }) => (
+            StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+            body,
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
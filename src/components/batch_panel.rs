@@ -0,0 +1,151 @@
+use super::error_notice::describe_error;
+use super::field_error::FieldErrorText;
+use super::trip_table::{TripColumn, TripTable};
+use super::util::cancel_task;
+use dioxus::prelude::*;
+use frontend_local_rust_os::api::backend::SharedBackend;
+use frontend_local_rust_os::api::batch::{self, get_by_indices};
+use frontend_local_rust_os::api::error::ApiError;
+use frontend_local_rust_os::api::query::parse_indices;
+use frontend_local_rust_os::api::trip::Trip;
+use frontend_local_rust_os::config::AppConfig;
+use futures::StreamExt;
+
+// Columnas de la tabla del lote
+const BATCH_COLUMNS: &[TripColumn] = &[
+    TripColumn::Index,
+    TripColumn::Pickup,
+    TripColumn::Origin,
+    TripColumn::Destination,
+    TripColumn::Distance,
+    TripColumn::Total,
+];
+
+/// Búsqueda por lotes: varios índices consultados en paralelo, con los fallos
+/// listados aparte.
+#[component]
+pub fn BatchPanel() -> Element {
+    let backend = use_signal(consume_context::<SharedBackend>);
+    let concurrency = use_signal(|| batch::concurrency(&consume_context::<AppConfig>().http));
+    let mut indices = use_signal(|| "1-20".to_string());
+    let mut trips = use_signal(Vec::<Trip>::new);
+    let mut failed = use_signal(Vec::<(u32, ApiError)>::new);
+    let mut requested = use_signal(|| 0usize);
+    let mut cancelled = use_signal(|| false);
+    let mut loading = use_signal(|| false);
+    let mut task = use_signal(|| None::<Task>);
+
+    let run = move |_| {
+        let Ok(list) = parse_indices(&indices.peek()) else {
+            return;
+        };
+        cancel_task(task);
+        trips.set(Vec::new());
+        failed.set(Vec::new());
+        requested.set(list.len());
+        cancelled.set(false);
+        loading.set(true);
+        let backend = backend();
+
+        let batch = spawn(async move {
+            let mut results = std::pin::pin!(get_by_indices(backend, list, concurrency()));
+            while let Some((index, result)) = results.next().await {
+                match result {
+                    Ok(trip) => trips.write().push(trip),
+                    Err(e) => failed.write().push((index, e)),
+                }
+            }
+            loading.set(false);
+            task.set(None);
+        });
+        task.set(Some(batch));
+    };
+
+    // Lo ya recibido se conserva
+    let cancel = move |_| {
+        cancel_task(task);
+        loading.set(false);
+        cancelled.set(true);
+    };
+
+    let error = parse_indices(&indices()).err();
+    let done = trips.read().len() + failed.read().len();
+
+    rsx! {
+        div {
+            style: "margin-top: 30px; padding: 20px; background-color: #D9A0AF; border-radius: 8px; box-shadow: 0 4px 8px rgba(0,0,0,0.2);",
+
+            h2 {
+                style: "color: #012606; margin-bottom: 20px;",
+                "Get by Index (lote)"
+            }
+
+            div {
+                style: "margin-bottom: 15px;",
+                label {
+                    style: "color: #012606; display: block; margin-bottom: 5px;",
+                    "Índices a buscar:"
+                }
+                input {
+                    style: "width: 95%; padding: 8px; border: 1px solid #012606; border-radius: 4px; background-color: rgba(255,255,255,0.9);",
+                    placeholder: "Ej: 100-250, 900, 1024",
+                    value: indices,
+                    oninput: move |evt| indices.set(evt.value().clone()),
+                }
+                FieldErrorText { error }
+            }
+
+            div {
+                style: "display: flex; gap: 10px; margin-bottom: 15px;",
+
+                button {
+                    style: "padding: 10px 20px; background-color: #0378A6; color: white; border: none; border-radius: 4px; cursor: pointer; flex: 1; font-weight: bold; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
+                    disabled: loading() || error.is_some(),
+                    onclick: run,
+                    {if loading() { format!("Cargando... ({} de {})", done, requested()) } else { "Obtener viajes".to_string() }}
+                }
+
+                if loading() {
+                    button {
+                        style: "padding: 10px; background-color: #BF5F56; color: white; border: none; border-radius: 4px; cursor: pointer; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
+                        onclick: cancel,
+                        "Cancelar"
+                    }
+                }
+            }
+
+            if cancelled() {
+                p {
+                    style: "color: #012606;",
+                    "Lote cancelado tras {done} de {requested()} índices."
+                }
+            }
+
+            if !failed.read().is_empty() {
+                div {
+                    style: "margin-top: 15px; padding: 10px; border-radius: 4px; background-color: #f8d7da; color: #721c24; border-left: 4px solid #BF5F56;",
+                    p { style: "margin: 0 0 5px 0; font-weight: bold;", "{failed.read().len()} índices fallaron:" }
+                    ul {
+                        style: "margin: 0; padding-left: 20px;",
+                        for (index, error) in failed() {
+                            li { key: "{index}", "{index}: {describe_error(&error).0}" }
+                        }
+                    }
+                }
+            }
+
+            if !trips.read().is_empty() {
+                div {
+                    style: "margin-top: 15px;",
+
+                    div {
+                        style: "padding: 10px; border-radius: 4px; background-color: #739FD9; color: white; margin-bottom: 10px;",
+                        p { "{trips.read().len()} viajes encontrados de {requested()} índices" }
+                    }
+
+                    TripTable { trips: trips(), columns: BATCH_COLUMNS }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use dioxus::prelude::*;
+use frontend_local_rust_os::api::breaker::{BreakerStatus, CircuitState};
+
+/// Estado del cortocircuito para la cabecera.
+#[component]
+pub fn BreakerBadge(status: BreakerStatus) -> Element {
+    let (color, label) = match status.state {
+        CircuitState::Closed if status.consecutive_failures > 0 => (
+            "#f9a825",
+            format!("Circuito cerrado, {} fallos", status.consecutive_failures),
+        ),
+        CircuitState::Closed => ("#2e7d32", "Circuito cerrado".to_string()),
+        CircuitState::Open => (
+            "#c62828",
+            format!(
+                "Circuito abierto, {} s",
+                status.retry_in().unwrap_or_default().as_secs() + 1
+            ),
+        ),
+        CircuitState::HalfOpen if status.probing => {
+            ("#f9a825", "Circuito semiabierto, probando".to_string())
+        }
+        CircuitState::HalfOpen => ("#f9a825", "Circuito semiabierto".to_string()),
+    };
+
+    rsx! {
+        span {
+            style: "margin-left: 10px; padding: 2px 8px; border-radius: 10px; background-color: {color}; color: white; font-size: 0.85em;",
+            "⚡ {label}"
+        }
+    }
+}
+
+// Cuándo se vuelve a probar el backend con el cortocircuito abierto
+pub fn breaker_progress(status: &BreakerStatus) -> String {
+    match (status.state, status.retry_in()) {
+        (CircuitState::Open, Some(left)) => {
+            format!("Se volverá a probar en {} s.", left.as_secs() + 1)
+        }
+        _ if status.probing => "Comprobando si el backend volvió...".to_string(),
+        _ => "La próxima consulta comprobará si el backend volvió.".to_string(),
+    }
+}
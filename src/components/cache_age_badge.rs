@@ -0,0 +1,28 @@
+use dioxus::prelude::*;
+use std::time::Duration;
+
+/// Aviso de que el resultado viene de la caché, con su antigüedad; nada si
+/// la respuesta es nueva.
+#[component]
+pub fn CacheAgeBadge(age: Option<Duration>) -> Element {
+    let Some(age) = age else {
+        return rsx! {};
+    };
+    rsx! {
+        span {
+            style: "margin-left: 8px; padding: 2px 6px; border-radius: 4px; background-color: #012606; color: white; font-size: 0.85em;",
+            "Desde caché ({format_age(age)})"
+        }
+    }
+}
+
+// Antigüedad legible de una respuesta guardada
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..60 => format!("hace {} s", secs),
+        60..3600 => format!("hace {} min", secs / 60),
+        3600..86400 => format!("hace {} h", secs / 3600),
+        _ => format!("hace {} días", secs / 86400),
+    }
+}
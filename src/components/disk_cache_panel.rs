@@ -0,0 +1,153 @@
+use dioxus::prelude::*;
+use frontend_local_rust_os::api::backend::SharedBackend;
+use frontend_local_rust_os::api::disk_cache::{DiskCache, DiskCacheEntry};
+use std::sync::Arc;
+
+/// Contenido de la caché en disco, con opciones para borrar entradas o vaciarla.
+#[component]
+pub fn DiskCachePanel() -> Element {
+    let backend = use_signal(consume_context::<SharedBackend>);
+    let disk_cache = use_signal(consume_context::<Option<Arc<DiskCache>>>);
+    let mut entries = use_signal(|| Ok(Vec::new()));
+
+    // Los cambios se aplican fuera del hilo de la interfaz y la lista se
+    // vuelve a leer al terminar
+    let update = move |change: Change| {
+        let backend = backend.peek().clone();
+        let cache = disk_cache.peek().clone();
+        spawn(async move {
+            entries.set(apply(backend, cache, change).await);
+        });
+    };
+    use_hook(|| update(Change::None));
+
+    let Some(cache) = disk_cache() else {
+        return rsx! {};
+    };
+    let path = cache.path().display().to_string();
+    let limit = format_size(cache.max_bytes());
+
+    rsx! {
+        div {
+            style: "margin-top: 30px; padding: 20px; background-color: #D9A0AF; border-radius: 8px; box-shadow: 0 4px 8px rgba(0,0,0,0.2); margin-bottom: 30px; color: #012606;",
+
+            h2 {
+                style: "color: #012606; margin-bottom: 10px;",
+                "Caché en disco"
+            }
+
+            p { style: "margin: 0 0 15px 0; word-break: break-all;", "Archivo: {path}" }
+
+            div {
+                style: "display: flex; gap: 10px; margin-bottom: 15px;",
+
+                button {
+                    style: "padding: 10px 20px; background-color: #0378A6; color: white; border: none; border-radius: 4px; cursor: pointer; font-weight: bold; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
+                    onclick: move |_| update(Change::None),
+                    "Actualizar"
+                }
+
+                button {
+                    style: "padding: 10px 20px; background-color: #BF5F56; color: white; border: none; border-radius: 4px; cursor: pointer; font-weight: bold; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
+                    onclick: move |_| update(Change::Clear),
+                    "Vaciar caché"
+                }
+            }
+
+            match entries() {
+                Err(message) => rsx! {
+                    p { style: "color: #721c24;", "No se pudo leer la caché: {message}" }
+                },
+                Ok(list) if list.is_empty() => rsx! {
+                    p { "No hay respuestas guardadas." }
+                },
+                Ok(list) => {
+                    let total: u64 = list.iter().map(|entry| entry.size).sum();
+                    rsx! {
+                        p { "{list.len()} respuestas guardadas | {format_size(total)} de {limit}" }
+
+                        table {
+                            style: "width: 100%; border-collapse: collapse; background-color: white; border-radius: 4px; overflow: hidden;",
+
+                            thead {
+                                tr {
+                                    style: "background-color: #012606; color: white;",
+                                    th { style: "padding: 10px; text-align: left; border-bottom: 1px solid #739FD9;", "Consulta" }
+                                    th { style: "padding: 10px; text-align: left; border-bottom: 1px solid #739FD9;", "Guardada" }
+                                    th { style: "padding: 10px; text-align: left; border-bottom: 1px solid #739FD9;", "Tamaño" }
+                                    th { style: "padding: 10px; text-align: left; border-bottom: 1px solid #739FD9;", "" }
+                                }
+                            }
+
+                            tbody {
+                                {list.into_iter().map(|entry| {
+                                    let stored_at = chrono::DateTime::<chrono::Local>::from(entry.stored_at)
+                                        .format("%d/%m/%Y %H:%M");
+                                    let key = entry.key.clone();
+                                    rsx! {
+                                        tr {
+                                            key: "{entry.key}",
+                                            td { style: "padding: 8px; text-align: left; border-bottom: 1px solid #D9A0AF; color: #012606; word-break: break-all;", "{entry.key}" }
+                                            td { style: "padding: 8px; text-align: left; border-bottom: 1px solid #D9A0AF; color: #012606;", "{stored_at}" }
+                                            td { style: "padding: 8px; text-align: left; border-bottom: 1px solid #D9A0AF; color: #012606;", "{format_size(entry.size)}" }
+                                            td {
+                                                style: "padding: 8px; text-align: left; border-bottom: 1px solid #D9A0AF;",
+                                                button {
+                                                    style: "padding: 4px 10px; background-color: #BF5F56; color: white; border: none; border-radius: 4px; cursor: pointer;",
+                                                    onclick: move |_| update(Change::Remove(key.clone())),
+                                                    "Borrar"
+                                                }
+                                            }
+                                        }
+                                    }
+                                })}
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Cambio pedido desde el panel
+enum Change {
+    None,
+    Remove(String),
+    Clear,
+}
+
+// redb bloquea al leer y al escribir el archivo, así que todo va al pool de
+// tareas bloqueantes
+async fn apply(
+    backend: SharedBackend,
+    cache: Option<Arc<DiskCache>>,
+    change: Change,
+) -> Result<Vec<DiskCacheEntry>, String> {
+    let Some(cache) = cache else {
+        return Ok(Vec::new());
+    };
+    tokio::task::spawn_blocking(move || {
+        match change {
+            Change::None => {}
+            Change::Remove(key) => {
+                if let Err(e) = cache.remove(&key) {
+                    tracing::warn!(key = %key, error = %e, "disk cache: cannot remove response");
+                }
+            }
+            // Vacía también la caché en memoria
+            Change::Clear => backend.clear_cache(),
+        }
+        cache.entries().map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{} B", bytes),
+        1024..1_048_576 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
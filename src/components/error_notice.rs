@@ -0,0 +1,123 @@
+use dioxus::prelude::*;
+use frontend_local_rust_os::api::backend::SharedBackend;
+use frontend_local_rust_os::api::error::ApiError;
+
+/// Aviso de error con un mensaje y una acción de recuperación según el tipo de fallo.
+#[component]
+pub fn ErrorNotice(context: String, error: ApiError, on_retry: EventHandler<()>) -> Element {
+    let backend = use_signal(consume_context::<SharedBackend>);
+    let mut authenticating = use_signal(|| false);
+    let mut auth_note = use_signal(|| None::<String>);
+
+    let (message, hint) = describe_error(&error);
+    let can_retry = error.is_outage();
+
+    // Credenciales rechazadas: estado propio, con la opción de renovarlas
+    if matches!(error, ApiError::Unauthorized(_)) {
+        let reauthenticate = move |_| {
+            authenticating.set(true);
+            auth_note.set(None);
+            spawn(async move {
+                let backend = backend();
+                match backend.reauthenticate().await {
+                    Ok(true) => on_retry.call(()),
+                    Ok(false) => auth_note.set(Some(
+                        "No hay un comando de renovación de token configurado (token_command). Actualiza TRIP_API_TOKEN, TRIP_API_KEY o la sección [auth] y reinicia la aplicación.".to_string(),
+                    )),
+                    Err(e) => auth_note.set(Some(describe_error(&e).0)),
+                }
+                authenticating.set(false);
+            });
+        };
+        return rsx! {
+            div {
+                style: "margin-top: 15px; padding: 10px; border-radius: 4px; background-color: #fff3cd; color: #856404; border-left: 4px solid #F2B705;",
+                p { style: "margin: 0 0 5px 0; font-weight: bold;", "{context}: sesión no válida" }
+                p { style: "margin: 0 0 5px 0;", "{message}" }
+                p { style: "margin: 0;", "{hint}" }
+
+                if let Some(note) = auth_note() {
+                    p { style: "margin: 5px 0 0 0; font-style: italic;", "{note}" }
+                }
+
+                button {
+                    style: "margin-top: 10px; padding: 6px 14px; background-color: #F2B705; color: #012606; border: none; border-radius: 4px; cursor: pointer; font-weight: bold;",
+                    disabled: authenticating(),
+                    onclick: reauthenticate,
+                    if authenticating() { "Autenticando..." } else { "Volver a autenticar" }
+                }
+            }
+        };
+    }
+
+    rsx! {
+        div {
+            style: "margin-top: 15px; padding: 10px; border-radius: 4px; background-color: #f8d7da; color: #721c24; border-left: 4px solid #BF5F56;",
+            p { style: "margin: 0 0 5px 0; font-weight: bold;", "{context}: {message}" }
+            p { style: "margin: 0;", "{hint}" }
+
+            if can_retry {
+                button {
+                    style: "margin-top: 10px; padding: 6px 14px; background-color: #BF5F56; color: white; border: none; border-radius: 4px; cursor: pointer;",
+                    onclick: move |_| on_retry.call(()),
+                    "Reintentar"
+                }
+            }
+        }
+    }
+}
+
+// Mensaje para el usuario y sugerencia de recuperación para cada variante de error
+pub fn describe_error(error: &ApiError) -> (String, &'static str) {
+    match error {
+        ApiError::Transport(detail) => (
+            format!("No se pudo conectar con el backend ({})", detail),
+            "Verifica tu conexión a Internet o la URL configurada del backend y reintenta.",
+        ),
+        ApiError::Timeout => (
+            "El backend tardó demasiado en responder.".to_string(),
+            "Puede estar arrancando en frío; espera unos segundos y reintenta.",
+        ),
+        ApiError::NotFound(_) => (
+            "No existe ningún viaje para esa consulta.".to_string(),
+            "Revisa el índice o el ID de destino e intenta con otro valor.",
+        ),
+        ApiError::Http { status, body } if *status >= 500 => (
+            format!("El backend falló con el estado {}: {}", status, body),
+            "Es un error del servidor; reintenta en unos momentos.",
+        ),
+        ApiError::Http { status, body } => (
+            format!(
+                "El backend rechazó la consulta (estado {}): {}",
+                status, body
+            ),
+            "Revisa los parámetros de búsqueda (precios, página y resultados por página).",
+        ),
+        ApiError::Unauthorized(detail) => (
+            format!("El backend rechazó las credenciales ({})", detail),
+            "Vuelve a autenticarte; si sigue fallando, revisa TRIP_API_TOKEN, TRIP_API_KEY o la sección [auth] de la configuración.",
+        ),
+        ApiError::Decode(detail) => (
+            format!(
+                "La respuesta del backend no tiene el formato esperado ({})",
+                detail
+            ),
+            "El backend puede haber cambiado de versión; avisa al equipo responsable.",
+        ),
+        ApiError::Unavailable { retry_in_secs: 0 } => (
+            "El backend no está disponible tras varios fallos seguidos; se está comprobando si volvió.".to_string(),
+            "Reintenta en unos segundos; mientras tanto se muestran las copias guardadas cuando existen.",
+        ),
+        ApiError::Unavailable { retry_in_secs } => (
+            format!(
+                "El backend no está disponible tras varios fallos seguidos; se volverá a probar en {} s.",
+                retry_in_secs
+            ),
+            "La consulta no se envió para no esperar a un backend caído; mientras tanto se muestran las copias guardadas cuando existen.",
+        ),
+        ApiError::Cassette(detail) => (
+            format!("La grabación de respuestas no cubre esta consulta: {}", detail),
+            "Vuelve a grabar con --record contra el backend real, o repite solo consultas ya grabadas.",
+        ),
+    }
+}
@@ -0,0 +1,41 @@
+use dioxus::prelude::*;
+use frontend_local_rust_os::api::query::{
+    FieldError, MAX_BATCH_INDICES, MAX_DESTINATIONS, PER_PAGE_OPTIONS,
+};
+
+/// Mensaje bajo un campo del formulario cuando su valor no es válido.
+#[component]
+pub fn FieldErrorText(error: Option<FieldError>) -> Element {
+    let Some(error) = error else {
+        return rsx! {};
+    };
+
+    rsx! {
+        p {
+            style: "margin: 4px 0 0 0; padding: 2px 6px; display: inline-block; border-radius: 4px; background-color: #f8d7da; color: #721c24; font-size: 0.85em;",
+            "{describe_field_error(error)}"
+        }
+    }
+}
+
+fn describe_field_error(error: FieldError) -> String {
+    match error {
+        FieldError::Empty => "Campo obligatorio".to_string(),
+        FieldError::NotANumber => "Debe ser un número".to_string(),
+        FieldError::NotAnInteger => "Debe ser un número entero".to_string(),
+        FieldError::Negative => "No puede ser negativo".to_string(),
+        FieldError::TooLarge => "Es demasiado grande".to_string(),
+        FieldError::BelowOne => "Debe ser 1 o más".to_string(),
+        FieldError::PageSize => {
+            let options: Vec<String> = PER_PAGE_OPTIONS.iter().map(u32::to_string).collect();
+            format!("Debe ser uno de: {}", options.join(", "))
+        }
+        FieldError::MinAboveMax => "No puede ser menor que el mínimo".to_string(),
+        FieldError::ReversedRange => "El inicio del rango es mayor que el final".to_string(),
+        FieldError::TooManyDestinations => format!("Como máximo {} destinos", MAX_DESTINATIONS),
+        FieldError::TooManyIndices => format!("Como máximo {} índices", MAX_BATCH_INDICES),
+        FieldError::NotADate => "Fecha no válida (AAAA-MM-DD)".to_string(),
+        FieldError::NotAnHour => "Debe ser una hora de 0 a 23".to_string(),
+        FieldError::NoSource => "Indica destinos o un rango de precio completo".to_string(),
+    }
+}
@@ -0,0 +1,411 @@
+use super::cache_age_badge::CacheAgeBadge;
+use super::error_notice::ErrorNotice;
+use super::field_error::FieldErrorText;
+use super::rejected_records::RejectedRecords;
+use super::trip_table::{TripColumn, TripTable};
+use super::util::{cancel_task, with_refresh};
+use dioxus::prelude::*;
+use frontend_local_rust_os::api::backend::SharedBackend;
+use frontend_local_rust_os::api::error::ApiError;
+use frontend_local_rust_os::api::filter::{filter_trips, FilteredTrips, DEFAULT_SCAN_LIMIT};
+use frontend_local_rust_os::api::page::PageRequest;
+use frontend_local_rust_os::api::paging::{PageProgress, PageStreamOptions};
+use frontend_local_rust_os::api::query::{DaysForm, FieldError, FilterForm, PER_PAGE_OPTIONS};
+use frontend_local_rust_os::api::trip::PaymentType;
+
+// Columnas de la tabla de resultados filtrados
+const FILTER_COLUMNS: &[TripColumn] = &[
+    TripColumn::Index,
+    TripColumn::Pickup,
+    TripColumn::Origin,
+    TripColumn::Destination,
+    TripColumn::Passengers,
+    TripColumn::Distance,
+    TripColumn::Payment,
+    TripColumn::Tip,
+    TripColumn::Total,
+];
+
+// Tipos de pago que se pueden elegir en el filtro
+const PAYMENT_OPTIONS: [PaymentType; 4] = [
+    PaymentType::CreditCard,
+    PaymentType::Cash,
+    PaymentType::NoCharge,
+    PaymentType::Dispute,
+];
+
+/// Búsqueda combinada: una consulta al backend filtrada después en el cliente
+/// por fechas, zona de recogida, pago, pasajeros, distancia y propina.
+#[component]
+pub fn FilterPanel() -> Element {
+    let backend = use_signal(consume_context::<SharedBackend>);
+    let mut form = use_signal(|| FilterForm {
+        destinations: "236".to_string(),
+        ..FilterForm::default()
+    });
+    let mut paging = use_signal(|| PageRequest::first(10));
+    let mut results = use_signal(|| None::<FilteredTrips>);
+    let mut loading = use_signal(|| false);
+    let mut error = use_signal(|| None::<ApiError>);
+    let mut progress = use_signal(|| None::<PageProgress>);
+    let mut task = use_signal(|| None::<Task>);
+
+    // Recorre todas las páginas de la consulta de origen; después la
+    // paginación es local
+    let mut run = move |refresh: bool| {
+        let Ok((source, filter)) = form.peek().parse() else {
+            return;
+        };
+        cancel_task(task);
+        loading.set(true);
+        error.set(None);
+        progress.set(None);
+        let backend = backend();
+        let options = PageStreamOptions {
+            max_items: Some(DEFAULT_SCAN_LIMIT),
+            ..PageStreamOptions::default()
+        };
+
+        let search = spawn(async move {
+            let on_progress = move |update: &PageProgress| progress.set(Some(*update));
+            match with_refresh(
+                refresh,
+                filter_trips(backend, source, &filter, options, on_progress),
+            )
+            .await
+            {
+                Ok(filtered) => {
+                    paging.with_mut(|paging| *paging = PageRequest::first(paging.per_page));
+                    results.set(Some(filtered));
+                }
+                Err(e) => {
+                    results.set(None);
+                    error.set(Some(e));
+                }
+            }
+            loading.set(false);
+            task.set(None);
+        });
+        task.set(Some(search));
+    };
+
+    let cancel = move |_| {
+        cancel_task(task);
+        loading.set(false);
+    };
+
+    let parsed = form.read().parse();
+    let valid = parsed.is_ok();
+    let errors = parsed.err().unwrap_or_default();
+    let page = results().map(|filtered| filtered.page(paging()));
+    let has_prev = page.as_ref().is_some_and(|page| page.has_prev());
+    let has_next = page.as_ref().is_some_and(|page| page.has_next());
+    let status = match progress() {
+        Some(progress) if loading() => format!(
+            "Revisando viajes... ({} de {})",
+            progress.items_fetched,
+            progress.items_total.min(DEFAULT_SCAN_LIMIT as u32)
+        ),
+        _ if loading() => "Cargando...".to_string(),
+        _ => "Buscar con filtros".to_string(),
+    };
+
+    rsx! {
+        div {
+            style: "margin-top: 30px; padding: 20px; background-color: #739FD9; border-radius: 8px; box-shadow: 0 4px 8px rgba(0,0,0,0.2); margin-bottom: 30px;",
+
+            h2 {
+                style: "color: #012606; margin-bottom: 10px;",
+                "Búsqueda con filtros"
+            }
+
+            p {
+                style: "margin: 0 0 15px 0;",
+                "La consulta al backend usa los destinos o, si no se indican, el rango de precio. El resto de filtros se aplica en la aplicación."
+            }
+
+            div {
+                style: "display: grid; grid-template-columns: 1fr 1fr 1fr; gap: 10px; margin-bottom: 15px;",
+
+                FilterField {
+                    label: "IDs de destino:",
+                    placeholder: "Ej: 236 o 1, 132, 138",
+                    value: form().destinations,
+                    error: errors.destinations,
+                    oninput: move |value| form.with_mut(|form| form.destinations = value),
+                }
+                FilterField {
+                    label: "Precio mínimo:",
+                    placeholder: "Ej: 10",
+                    value: form().min_price,
+                    error: errors.min_price,
+                    oninput: move |value| form.with_mut(|form| form.min_price = value),
+                }
+                FilterField {
+                    label: "Precio máximo:",
+                    placeholder: "Ej: 20",
+                    value: form().max_price,
+                    error: errors.max_price,
+                    oninput: move |value| form.with_mut(|form| form.max_price = value),
+                }
+                FilterField {
+                    label: "Recogida desde:",
+                    placeholder: "AAAA-MM-DD",
+                    value: form().pickup_from,
+                    error: errors.pickup_from,
+                    oninput: move |value| form.with_mut(|form| form.pickup_from = value),
+                }
+                FilterField {
+                    label: "Recogida hasta:",
+                    placeholder: "AAAA-MM-DD",
+                    value: form().pickup_to,
+                    error: errors.pickup_to,
+                    oninput: move |value| form.with_mut(|form| form.pickup_to = value),
+                }
+                div {
+                    label {
+                        style: "color: white; display: block; margin-bottom: 5px;",
+                        "Días:"
+                    }
+                    select {
+                        style: "width: 90%; padding: 8px; border: 1px solid #012606; border-radius: 4px; background-color: rgba(255,255,255,0.9);",
+                        oninput: move |evt| {
+                            let days = match evt.value().as_str() {
+                                "workdays" => DaysForm::Workdays,
+                                "weekend" => DaysForm::Weekend,
+                                _ => DaysForm::Any,
+                            };
+                            form.with_mut(|form| form.days = days);
+                        },
+                        option { value: "any", selected: form().days == DaysForm::Any, "Todos" }
+                        option { value: "workdays", selected: form().days == DaysForm::Workdays, "De lunes a viernes" }
+                        option { value: "weekend", selected: form().days == DaysForm::Weekend, "Fin de semana" }
+                    }
+                }
+                FilterField {
+                    label: "Llegada desde:",
+                    placeholder: "AAAA-MM-DD",
+                    value: form().dropoff_from,
+                    error: errors.dropoff_from,
+                    oninput: move |value| form.with_mut(|form| form.dropoff_from = value),
+                }
+                FilterField {
+                    label: "Llegada hasta:",
+                    placeholder: "AAAA-MM-DD",
+                    value: form().dropoff_to,
+                    error: errors.dropoff_to,
+                    oninput: move |value| form.with_mut(|form| form.dropoff_to = value),
+                }
+                FilterField {
+                    label: "Zonas de recogida:",
+                    placeholder: "Ej: 132, 138",
+                    value: form().pickup_zones,
+                    error: errors.pickup_zones,
+                    oninput: move |value| form.with_mut(|form| form.pickup_zones = value),
+                }
+                FilterField {
+                    label: "Hora de recogida desde:",
+                    placeholder: "0-23, ej: 6",
+                    value: form().hour_from,
+                    error: errors.hour_from,
+                    oninput: move |value| form.with_mut(|form| form.hour_from = value),
+                }
+                FilterField {
+                    label: "Hora de recogida hasta:",
+                    placeholder: "0-23, ej: 11",
+                    value: form().hour_to,
+                    error: errors.hour_to,
+                    oninput: move |value| form.with_mut(|form| form.hour_to = value),
+                }
+                div {
+                    label {
+                        style: "color: white; display: block; margin-bottom: 5px;",
+                        "Tipo de pago:"
+                    }
+                    select {
+                        style: "width: 90%; padding: 8px; border: 1px solid #012606; border-radius: 4px; background-color: rgba(255,255,255,0.9);",
+                        oninput: move |evt| {
+                            let payment = evt
+                                .value()
+                                .parse::<usize>()
+                                .ok()
+                                .and_then(|i| PAYMENT_OPTIONS.get(i).copied());
+                            form.with_mut(|form| form.payment = payment);
+                        },
+                        option { value: "", selected: form().payment.is_none(), "Cualquiera" }
+                        for (i, payment) in PAYMENT_OPTIONS.into_iter().enumerate() {
+                            option {
+                                value: "{i}",
+                                selected: form().payment == Some(payment),
+                                "{payment.label()}"
+                            }
+                        }
+                    }
+                }
+                FilterField {
+                    label: "Pasajeros mínimo:",
+                    placeholder: "Ej: 1",
+                    value: form().min_passengers,
+                    error: errors.min_passengers,
+                    oninput: move |value| form.with_mut(|form| form.min_passengers = value),
+                }
+                FilterField {
+                    label: "Pasajeros máximo:",
+                    placeholder: "Ej: 2",
+                    value: form().max_passengers,
+                    error: errors.max_passengers,
+                    oninput: move |value| form.with_mut(|form| form.max_passengers = value),
+                }
+                FilterField {
+                    label: "Distancia mínima (millas):",
+                    placeholder: "Ej: 1.5",
+                    value: form().min_distance,
+                    error: errors.min_distance,
+                    oninput: move |value| form.with_mut(|form| form.min_distance = value),
+                }
+                FilterField {
+                    label: "Distancia máxima (millas):",
+                    placeholder: "Ej: 10",
+                    value: form().max_distance,
+                    error: errors.max_distance,
+                    oninput: move |value| form.with_mut(|form| form.max_distance = value),
+                }
+                FilterField {
+                    label: "Propina mínima:",
+                    placeholder: "Ej: 2",
+                    value: form().min_tip,
+                    error: errors.min_tip,
+                    oninput: move |value| form.with_mut(|form| form.min_tip = value),
+                }
+                FilterField {
+                    label: "Propina máxima:",
+                    placeholder: "Ej: 10",
+                    value: form().max_tip,
+                    error: errors.max_tip,
+                    oninput: move |value| form.with_mut(|form| form.max_tip = value),
+                }
+            }
+
+            div {
+                style: "display: flex; gap: 10px; margin-bottom: 15px;",
+
+                button {
+                    style: "padding: 10px 20px; background-color: #BF5F56; color: white; border: none; border-radius: 4px; cursor: pointer; flex: 1; font-weight: bold; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
+                    disabled: loading() || !valid,
+                    onclick: move |_| run(false),
+                    "{status}"
+                }
+
+                if loading() {
+                    button {
+                        style: "padding: 10px; background-color: #0378A6; color: white; border: none; border-radius: 4px; cursor: pointer; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
+                        onclick: cancel,
+                        "Cancelar"
+                    }
+                }
+
+                select {
+                    style: "padding: 10px; border: 1px solid #012606; border-radius: 4px; background-color: rgba(255,255,255,0.9);",
+                    title: "Resultados por página",
+                    oninput: move |evt| {
+                        if let Ok(per_page) = evt.value().parse() {
+                            paging.set(PageRequest::first(per_page));
+                        }
+                    },
+                    for size in PER_PAGE_OPTIONS {
+                        option { value: "{size}", selected: paging().per_page == size, "{size} por página" }
+                    }
+                }
+
+                button {
+                    style: "padding: 10px; background-color: #012606; color: white; border: none; border-radius: 4px; cursor: pointer; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
+                    disabled: !has_prev,
+                    onclick: move |_| {
+                        if let Some(request) = paging().prev() {
+                            paging.set(request);
+                        }
+                    },
+                    "<<"
+                }
+
+                button {
+                    style: "padding: 10px; background-color: #012606; color: white; border: none; border-radius: 4px; cursor: pointer; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
+                    disabled: !has_next,
+                    onclick: move |_| paging.set(paging().next()),
+                    ">>"
+                }
+
+                button {
+                    style: "padding: 10px; background-color: #012606; color: white; border: none; border-radius: 4px; cursor: pointer; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
+                    title: "Volver a consultar el backend sin usar la caché",
+                    disabled: loading() || !valid || results().is_none(),
+                    onclick: move |_| run(true),
+                    "Refrescar"
+                }
+            }
+
+            {error().map(|error| rsx! {
+                ErrorNotice {
+                    context: "Búsqueda con filtros",
+                    error,
+                    on_retry: move |_| run(false),
+                }
+            })}
+
+            if let (Some(filtered), Some(page)) = (results(), page) {
+                div {
+                    style: "margin-top: 15px;",
+
+                    div {
+                        style: "padding: 10px; border-radius: 4px; background-color: #D9A0AF; color: #012606; margin-bottom: 10px;",
+                        p {
+                            style: "margin: 0 0 5px 0;",
+                            "Cumplen los filtros {filtered.trips.len()} de {filtered.scanned} viajes revisados"
+                        }
+                        if filtered.truncated() {
+                            p {
+                                style: "margin: 0 0 5px 0; font-weight: bold;",
+                                "Solo se revisaron los primeros {filtered.scanned} de {filtered.source_total} viajes; acota la consulta para ver el resto."
+                            }
+                        }
+                        p {
+                            style: "margin: 0;",
+                            "Viajes {page.range_text()} | Página {page.page} de {page.pages} | Tiempo: {page.time_ms}ms"
+                            CacheAgeBadge { age: page.cache_age }
+                        }
+                    }
+
+                    RejectedRecords { rejected: filtered.rejected.clone() }
+
+                    TripTable { trips: page.items.clone(), columns: FILTER_COLUMNS }
+                }
+            }
+        }
+    }
+}
+
+/// Campo de texto del formulario de filtros, con su error debajo.
+#[component]
+fn FilterField(
+    label: String,
+    placeholder: String,
+    value: String,
+    error: Option<FieldError>,
+    oninput: EventHandler<String>,
+) -> Element {
+    rsx! {
+        div {
+            label {
+                style: "color: white; display: block; margin-bottom: 5px;",
+                "{label}"
+            }
+            input {
+                style: "width: 90%; padding: 8px; border: 1px solid #012606; border-radius: 4px; background-color: rgba(255,255,255,0.9);",
+                placeholder,
+                value,
+                oninput: move |evt| oninput.call(evt.value()),
+            }
+            FieldErrorText { error }
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use dioxus::prelude::*;
+use frontend_local_rust_os::api::health::{HealthState, HealthStatus};
+
+/// Indicador del estado del backend para la cabecera.
+#[component]
+pub fn HealthBadge(status: HealthStatus) -> Element {
+    let latency = status
+        .latency
+        .map(|latency| format!(" ({} ms)", latency.as_millis()))
+        .unwrap_or_default();
+    let (color, label) = match status.state {
+        HealthState::Unknown => return rsx! {},
+        HealthState::Up => ("#2e7d32", format!("En línea{}", latency)),
+        HealthState::Degraded if status.last_error.is_some() => {
+            ("#f9a825", "Con fallos".to_string())
+        }
+        HealthState::Degraded => ("#f9a825", format!("Lento{}", latency)),
+        HealthState::Down => ("#c62828", "Sin conexión".to_string()),
+    };
+
+    rsx! {
+        span {
+            style: "margin-left: 10px; padding: 2px 8px; border-radius: 10px; background-color: {color}; color: white; font-size: 0.85em;",
+            "● {label}"
+        }
+    }
+}
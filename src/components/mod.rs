@@ -1 +1,12 @@
-pub mod price_range_search;
+pub mod batch_panel;
+pub mod breaker_badge;
+pub mod cache_age_badge;
+pub mod disk_cache_panel;
+pub mod error_notice;
+pub mod field_error;
+pub mod filter_panel;
+pub mod health_badge;
+pub mod network_panel;
+pub mod rejected_records;
+pub mod trip_table;
+pub mod util;
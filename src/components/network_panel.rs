@@ -0,0 +1,86 @@
+use dioxus::prelude::*;
+use frontend_local_rust_os::api::inspector::{CallRecord, NetworkLog};
+
+/// Últimas llamadas al backend con sus tiempos, la más reciente primero.
+#[component]
+pub fn NetworkPanel() -> Element {
+    let network_log = use_signal(consume_context::<NetworkLog>);
+    let mut calls = use_signal(|| network_log.peek().calls());
+
+    // Refresca la tabla cada vez que termina una llamada
+    use_future(move || async move {
+        let mut changes = network_log.peek().subscribe();
+        while changes.changed().await.is_ok() {
+            calls.set(network_log.peek().calls());
+        }
+    });
+
+    rsx! {
+        div {
+            div {
+                style: "display: flex; justify-content: space-between; align-items: center; margin-bottom: 10px;",
+                h2 { style: "color: #012606; margin: 0;", "Inspector de red" }
+                button {
+                    style: "padding: 6px 14px; background-color: #BF5F56; color: white; border: none; border-radius: 4px; cursor: pointer;",
+                    onclick: move |_| network_log.peek().clear(),
+                    "Limpiar"
+                }
+            }
+
+            if calls().is_empty() {
+                p { "Aún no se ha llamado al backend." }
+            } else {
+                div {
+                    style: "overflow-x: auto; max-height: 300px; overflow-y: auto;",
+                    table {
+                        style: "width: 100%; border-collapse: collapse; background-color: white; font-family: monospace; font-size: 12px;",
+
+                        thead {
+                            tr {
+                                style: "background-color: #012606; color: white;",
+                                for header in ["Hora", "Endpoint", "Parámetros", "Estado", "Bytes", "Cliente ms", "Servidor ms", "Red ms", "Cola ms", "Intentos", "Error"] {
+                                    th { style: "padding: 6px; text-align: left;", "{header}" }
+                                }
+                            }
+                        }
+
+                        tbody {
+                            for call in calls().into_iter().rev() {
+                                NetworkRow { key: "{call.id}", call }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn NetworkRow(call: CallRecord) -> Element {
+    let started_at =
+        chrono::DateTime::<chrono::Local>::from(call.started_at).format("%H:%M:%S%.3f");
+    let show = |value: Option<u64>| value.map_or("-".to_string(), |value| value.to_string());
+    let status_color = match call.status {
+        Some(200..300) => "#155724",
+        Some(_) => "#721c24",
+        None if call.error.is_some() => "#721c24",
+        None => "#666",
+    };
+    let cell = "padding: 6px; border-bottom: 1px solid #ddd; text-align: left;";
+    rsx! {
+        tr {
+            td { style: cell, "{started_at}" }
+            td { style: cell, "{call.endpoint}" }
+            td { style: "{cell} word-break: break-all;", "{call.params}" }
+            td { style: "{cell} color: {status_color};", "{show(call.status.map(u64::from))}" }
+            td { style: cell, "{show(call.bytes)}" }
+            td { style: cell, "{show(call.client_ms)}" }
+            td { style: cell, "{show(call.server_ms)}" }
+            td { style: cell, "{show(call.overhead_ms())}" }
+            td { style: cell, "{show(call.queued_ms)}" }
+            td { style: cell, "{show(call.attempts)}" }
+            td { style: "{cell} color: #721c24;", "{call.error.clone().unwrap_or_default()}" }
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use dioxus::prelude::*;
+use frontend_local_rust_os::api::page::RejectedItem;
+
+/// Registros que el backend envió pero no se pudieron leer; el resto de la
+/// página se muestra igualmente.
+#[component]
+pub fn RejectedRecords(rejected: Vec<RejectedItem>) -> Element {
+    if rejected.is_empty() {
+        return rsx! {};
+    }
+    let summary = match rejected.len() {
+        1 => "1 registro no se pudo leer y no se muestra".to_string(),
+        count => format!("{} registros no se pudieron leer y no se muestran", count),
+    };
+
+    rsx! {
+        details {
+            style: "margin-bottom: 10px; padding: 10px; border-radius: 4px; background-color: #fff3cd; color: #856404; border-left: 4px solid #F2B705;",
+            summary { style: "cursor: pointer; font-weight: bold;", "{summary}" }
+            ul {
+                style: "margin: 8px 0 0 0; padding-left: 20px;",
+                for item in rejected {
+                    li {
+                        key: "{item.position}",
+                        style: "margin-bottom: 6px; word-break: break-all;",
+                        "Registro {item.position + 1}: {item.error}"
+                        br {}
+                        code { style: "font-size: 0.85em;", "{shorten(&item.raw, 200)}" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Recorta un texto largo a `max` caracteres
+fn shorten(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
@@ -0,0 +1,106 @@
+use dioxus::prelude::*;
+use frontend_local_rust_os::api::trip::{Field, PaymentType, Trip};
+use rust_decimal::Decimal;
+
+/// Columnas que puede mostrar una [`TripTable`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TripColumn {
+    Index,
+    Pickup,
+    Origin,
+    Destination,
+    Passengers,
+    Distance,
+    Payment,
+    Tip,
+    Total,
+}
+
+impl TripColumn {
+    /// Columnas de los listados por precio y por destino.
+    pub const SUMMARY: &'static [TripColumn] = &[
+        TripColumn::Index,
+        TripColumn::Origin,
+        TripColumn::Destination,
+        TripColumn::Distance,
+        TripColumn::Total,
+    ];
+
+    fn header(self) -> &'static str {
+        match self {
+            TripColumn::Index => "Índice",
+            TripColumn::Pickup => "Recogida",
+            TripColumn::Origin => "Origen",
+            TripColumn::Destination => "Destino",
+            TripColumn::Passengers => "Pasajeros",
+            TripColumn::Distance => "Distancia",
+            TripColumn::Payment => "Pago",
+            TripColumn::Tip => "Propina",
+            TripColumn::Total => "Importe",
+        }
+    }
+
+    fn cell(self, trip: &Trip) -> String {
+        match self {
+            TripColumn::Index => trip.index.to_string(),
+            TripColumn::Pickup => trip.tpep_pickup_datetime.to_string(),
+            TripColumn::Origin => trip.pu_location_id.to_string(),
+            TripColumn::Destination => trip.do_location_id.to_string(),
+            TripColumn::Passengers => trip.passenger_count.to_string(),
+            TripColumn::Distance => trip.trip_distance.to_string(),
+            TripColumn::Payment => trip
+                .payment_type
+                .get()
+                .map_or("-", PaymentType::label)
+                .to_string(),
+            TripColumn::Tip => format!("${}", format_money(&trip.tip_amount)),
+            TripColumn::Total => format!("${}", format_money(&trip.total_amount)),
+        }
+    }
+}
+
+/// Tabla de viajes con las columnas elegidas, en el orden dado.
+#[component]
+pub fn TripTable(trips: Vec<Trip>, columns: &'static [TripColumn]) -> Element {
+    rsx! {
+        table {
+            style: "width: 100%; border-collapse: collapse; background-color: white; border-radius: 4px; overflow: hidden;",
+
+            thead {
+                tr {
+                    style: "background-color: #012606; color: white;",
+                    for column in columns {
+                        th { style: "padding: 10px; text-align: left; border-bottom: 1px solid #739FD9;", "{column.header()}" }
+                    }
+                }
+            }
+
+            tbody {
+                for (position, trip) in trips.iter().enumerate() {
+                    tr {
+                        key: "{row_key(position, trip)}",
+                        for column in columns {
+                            td { style: "padding: 8px; text-align: left; border-bottom: 1px solid #D9A0AF; color: #012606;", "{column.cell(trip)}" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Clave de la fila: el índice del viaje, o su posición si el registro no lo trae
+fn row_key(position: usize, trip: &Trip) -> String {
+    match trip.index.raw().trim() {
+        "" => format!("#{}", position),
+        index => index.to_string(),
+    }
+}
+
+// Importe con dos decimales; si no se pudo interpretar se muestra el texto original
+pub fn format_money(amount: &Field<Decimal>) -> String {
+    match amount.value() {
+        Some(value) => format!("{:.2}", value),
+        None => amount.raw().to_string(),
+    }
+}
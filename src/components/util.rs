@@ -0,0 +1,19 @@
+use dioxus::prelude::*;
+use frontend_local_rust_os::api::cache::bypass_cache;
+use std::future::Future;
+
+// Cancela la consulta en curso de una sección; su resultado ya no se escribe
+pub fn cancel_task(mut task: Signal<Option<Task>>) {
+    if let Some(task) = task.take() {
+        task.cancel();
+    }
+}
+
+// Ejecuta la consulta saltándose la caché cuando se pidió refrescar
+pub async fn with_refresh<F: Future>(refresh: bool, future: F) -> F::Output {
+    if refresh {
+        bypass_cache(future).await
+    } else {
+        future.await
+    }
+}
@@ -0,0 +1,607 @@
+use crate::api::auth::{AuthConfig, Secret};
+use crate::api::breaker::BreakerConfig;
+use crate::api::cache::CacheConfig;
+use crate::api::cassette::{CassetteConfig, CassetteMode};
+use crate::api::disk_cache::DiskCacheConfig;
+use crate::api::health::HealthConfig;
+use crate::api::limiter::DEFAULT_MAX_CONCURRENT_REQUESTS;
+use crate::api::retry::RetryPolicy;
+use reqwest::Url;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Backend used when nothing else is configured.
+pub const DEFAULT_API_URL: &str = "https://backend-rust-277582128315.us-central1.run.app/";
+
+/// Environment variable that overrides the backend base URL.
+pub const API_URL_ENV: &str = "TRIP_API_URL";
+
+/// Environment variable pointing at a trip CSV to use instead of the backend.
+pub const OFFLINE_CSV_ENV: &str = "TRIP_OFFLINE_CSV";
+
+/// Environment variable with the API key; overrides the config file.
+pub const API_KEY_ENV: &str = "TRIP_API_KEY";
+
+/// Environment variable with the bearer token; overrides the config file.
+pub const API_TOKEN_ENV: &str = "TRIP_API_TOKEN";
+
+/// Environment variable pointing at a TOML config file.
+pub const CONFIG_PATH_ENV: &str = "TRIP_CONFIG";
+
+/// Config file looked up in the working directory when no path is given.
+pub const DEFAULT_CONFIG_FILE: &str = "frontend-local-rust-os.toml";
+
+/// Settings resolved once at startup and shared with the UI through context.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppConfig {
+    pub api_url: Url,
+    pub http: HttpConfig,
+    pub auth: AuthConfig,
+    pub retry: RetryPolicy,
+    pub cache: CacheConfig,
+    pub disk_cache: DiskCacheConfig,
+    pub health: HealthConfig,
+    pub breaker: BreakerConfig,
+    pub log: LogConfig,
+    /// When set, trips are served from this local CSV and no request leaves
+    /// the machine.
+    pub offline_csv: Option<PathBuf>,
+    /// Responses recorded to or replayed from a fixture file, set with
+    /// `--record` or `--replay`.
+    pub cassette: Option<CassetteConfig>,
+}
+
+/// Settings for the shared HTTP client.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpConfig {
+    pub connect_timeout: Duration,
+    /// Maximum time between two reads of the response.
+    pub read_timeout: Duration,
+    pub user_agent: String,
+    /// Headers sent with every request, on top of `Accept: application/json`.
+    pub headers: BTreeMap<String, String>,
+    /// Proxy for every request. When unset, the `HTTPS_PROXY`, `HTTP_PROXY`
+    /// and `NO_PROXY` environment variables apply.
+    pub proxy: Option<Url>,
+    /// Hosts reached without the proxy: names (a leading `.` matches
+    /// subdomains), IPs or CIDR ranges.
+    pub no_proxy: Vec<String>,
+    /// PEM files with root certificates trusted on top of the system ones.
+    pub ca_certs: Vec<PathBuf>,
+    /// Requests sent to the backend at once, the rest waiting in a queue;
+    /// `0` for no limit.
+    pub max_concurrent_requests: usize,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            connect_timeout: Duration::from_secs(10),
+            read_timeout: Duration::from_secs(30),
+            user_agent: concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")).to_string(),
+            headers: BTreeMap::new(),
+            proxy: None,
+            no_proxy: Vec::new(),
+            ca_certs: Vec::new(),
+            max_concurrent_requests: DEFAULT_MAX_CONCURRENT_REQUESTS,
+        }
+    }
+}
+
+/// Settings for the request log file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogConfig {
+    pub enabled: bool,
+    /// Directory of the log files; `None` when the platform has no data
+    /// directory.
+    pub dir: Option<PathBuf>,
+    pub rotation: LogRotation,
+    /// Log files kept; older ones are deleted on rotation.
+    pub max_files: usize,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            enabled: true,
+            dir: dirs::data_dir().map(|dir| dir.join(env!("CARGO_PKG_NAME")).join("logs")),
+            rotation: LogRotation::Daily,
+            max_files: 7,
+        }
+    }
+}
+
+/// How often a new log file is started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Hourly,
+    Daily,
+    Never,
+}
+
+/// Shape of the TOML config file. Every key is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ConfigFile {
+    api_url: Option<String>,
+    offline_csv: Option<PathBuf>,
+    http: HttpFile,
+    auth: AuthFile,
+    retry: RetryFile,
+    cache: CacheFile,
+    disk_cache: DiskCacheFile,
+    health: HealthFile,
+    breaker: BreakerFile,
+    log: LogFile,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct HttpFile {
+    connect_timeout_secs: Option<u64>,
+    read_timeout_secs: Option<u64>,
+    user_agent: Option<String>,
+    headers: BTreeMap<String, String>,
+    proxy: Option<String>,
+    no_proxy: Vec<String>,
+    ca_certs: Vec<PathBuf>,
+    max_concurrent_requests: Option<usize>,
+}
+
+impl HttpFile {
+    fn resolve(self) -> Result<HttpConfig, Box<dyn Error>> {
+        let defaults = HttpConfig::default();
+        // The URL may carry proxy credentials, so it stays out of the error
+        let proxy = match self.proxy.filter(|proxy| !proxy.trim().is_empty()) {
+            Some(proxy) => {
+                Some(Url::parse(proxy.trim()).map_err(|e| format!("Invalid proxy URL: {}", e))?)
+            }
+            None => None,
+        };
+        Ok(HttpConfig {
+            connect_timeout: self
+                .connect_timeout_secs
+                .map_or(defaults.connect_timeout, Duration::from_secs),
+            read_timeout: self
+                .read_timeout_secs
+                .map_or(defaults.read_timeout, Duration::from_secs),
+            user_agent: self.user_agent.unwrap_or(defaults.user_agent),
+            headers: self.headers,
+            proxy,
+            no_proxy: self.no_proxy,
+            ca_certs: self.ca_certs,
+            max_concurrent_requests: self
+                .max_concurrent_requests
+                .unwrap_or(defaults.max_concurrent_requests),
+        })
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AuthFile {
+    api_key: Option<Secret>,
+    api_key_header: Option<String>,
+    bearer_token: Option<Secret>,
+    token_command: Vec<String>,
+}
+
+impl AuthFile {
+    /// `TRIP_API_KEY` and `TRIP_API_TOKEN` win over the keys of the file.
+    fn resolve(self, env: &impl Fn(&str) -> Option<String>) -> AuthConfig {
+        let defaults = AuthConfig::default();
+        let from_env = |name| env(name).map(Secret::new);
+        AuthConfig {
+            api_key: from_env(API_KEY_ENV).or(self.api_key),
+            api_key_header: self.api_key_header.unwrap_or(defaults.api_key_header),
+            bearer_token: from_env(API_TOKEN_ENV).or(self.bearer_token),
+            token_command: self.token_command,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RetryFile {
+    max_attempts: Option<u32>,
+    initial_backoff_ms: Option<u64>,
+    max_backoff_ms: Option<u64>,
+    multiplier: Option<f64>,
+    jitter: Option<bool>,
+}
+
+impl RetryFile {
+    fn resolve(self) -> RetryPolicy {
+        let defaults = RetryPolicy::default();
+        RetryPolicy {
+            max_attempts: self.max_attempts.unwrap_or(defaults.max_attempts).max(1),
+            initial_backoff: self
+                .initial_backoff_ms
+                .map_or(defaults.initial_backoff, Duration::from_millis),
+            max_backoff: self
+                .max_backoff_ms
+                .map_or(defaults.max_backoff, Duration::from_millis),
+            multiplier: self.multiplier.unwrap_or(defaults.multiplier),
+            jitter: self.jitter.unwrap_or(defaults.jitter),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CacheFile {
+    enabled: Option<bool>,
+    ttl_secs: Option<u64>,
+    max_entries: Option<usize>,
+}
+
+impl CacheFile {
+    fn resolve(self) -> CacheConfig {
+        let defaults = CacheConfig::default();
+        CacheConfig {
+            enabled: self.enabled.unwrap_or(defaults.enabled),
+            ttl: self.ttl_secs.map_or(defaults.ttl, Duration::from_secs),
+            max_entries: self.max_entries.unwrap_or(defaults.max_entries),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct DiskCacheFile {
+    enabled: Option<bool>,
+    path: Option<PathBuf>,
+    max_size_mb: Option<u64>,
+}
+
+impl DiskCacheFile {
+    fn resolve(self) -> DiskCacheConfig {
+        let defaults = DiskCacheConfig::default();
+        DiskCacheConfig {
+            enabled: self.enabled.unwrap_or(defaults.enabled),
+            path: self.path.or(defaults.path),
+            max_bytes: self
+                .max_size_mb
+                .map_or(defaults.max_bytes, |mb| mb * 1024 * 1024),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct HealthFile {
+    enabled: Option<bool>,
+    interval_secs: Option<u64>,
+    down_interval_secs: Option<u64>,
+    timeout_secs: Option<u64>,
+    degraded_latency_ms: Option<u64>,
+    failures_before_down: Option<u32>,
+}
+
+impl HealthFile {
+    fn resolve(self) -> HealthConfig {
+        let defaults = HealthConfig::default();
+        HealthConfig {
+            enabled: self.enabled.unwrap_or(defaults.enabled),
+            interval: self
+                .interval_secs
+                .map_or(defaults.interval, Duration::from_secs),
+            down_interval: self
+                .down_interval_secs
+                .map_or(defaults.down_interval, Duration::from_secs),
+            timeout: self
+                .timeout_secs
+                .map_or(defaults.timeout, Duration::from_secs),
+            degraded_latency: self
+                .degraded_latency_ms
+                .map_or(defaults.degraded_latency, Duration::from_millis),
+            failures_before_down: self
+                .failures_before_down
+                .unwrap_or(defaults.failures_before_down)
+                .max(1),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct BreakerFile {
+    enabled: Option<bool>,
+    failure_threshold: Option<u32>,
+    cooldown_secs: Option<u64>,
+}
+
+impl BreakerFile {
+    fn resolve(self) -> BreakerConfig {
+        let defaults = BreakerConfig::default();
+        BreakerConfig {
+            enabled: self.enabled.unwrap_or(defaults.enabled),
+            failure_threshold: self
+                .failure_threshold
+                .unwrap_or(defaults.failure_threshold)
+                .max(1),
+            cooldown: self
+                .cooldown_secs
+                .map_or(defaults.cooldown, Duration::from_secs),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct LogFile {
+    enabled: Option<bool>,
+    dir: Option<PathBuf>,
+    rotation: Option<LogRotation>,
+    max_files: Option<usize>,
+}
+
+impl LogFile {
+    fn resolve(self) -> LogConfig {
+        let defaults = LogConfig::default();
+        LogConfig {
+            enabled: self.enabled.unwrap_or(defaults.enabled),
+            dir: self.dir.or(defaults.dir),
+            rotation: self.rotation.unwrap_or(defaults.rotation),
+            max_files: self.max_files.unwrap_or(defaults.max_files).max(1),
+        }
+    }
+}
+
+/// Values taken from the command line.
+#[derive(Debug, Default)]
+struct CliArgs {
+    api_url: Option<String>,
+    offline_csv: Option<PathBuf>,
+    config: Option<PathBuf>,
+    cassette: Option<CassetteConfig>,
+}
+
+impl AppConfig {
+    /// Resolves the configuration with the precedence
+    /// `--api-url` flag > `TRIP_API_URL` > config file > [`DEFAULT_API_URL`].
+    /// The offline CSV follows the same order with `--offline-csv` and
+    /// `TRIP_OFFLINE_CSV`.
+    ///
+    /// The config file is taken from `--config`, then `TRIP_CONFIG`, then
+    /// `frontend-local-rust-os.toml` in the working directory if it exists.
+    /// `--record <file>` and `--replay <file>` exist only on the command line.
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        Self::load_from(std::env::args().skip(1), |name| {
+            std::env::var(name).ok().filter(|value| !value.is_empty())
+        })
+    }
+
+    /// [`AppConfig::load`] from the given arguments and environment, where
+    /// `env` returns the non-empty value of a variable.
+    fn load_from(
+        args: impl Iterator<Item = String>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let cli = parse_args(args);
+
+        let config_path = cli
+            .config
+            .clone()
+            .or_else(|| env(CONFIG_PATH_ENV).map(PathBuf::from));
+        let file = match config_path {
+            Some(path) => read_config_file(&path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                read_config_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            None => ConfigFile::default(),
+        };
+
+        let api_url = cli
+            .api_url
+            .or_else(|| env(API_URL_ENV))
+            .or(file.api_url)
+            .unwrap_or_else(|| DEFAULT_API_URL.to_string());
+
+        let offline_csv = cli
+            .offline_csv
+            .or_else(|| env(OFFLINE_CSV_ENV).map(PathBuf::from))
+            .or(file.offline_csv);
+
+        Ok(AppConfig {
+            api_url: parse_base_url(&api_url)?,
+            http: file.http.resolve()?,
+            auth: file.auth.resolve(&env),
+            retry: file.retry.resolve(),
+            cache: file.cache.resolve(),
+            disk_cache: file.disk_cache.resolve(),
+            health: file.health.resolve(),
+            breaker: file.breaker.resolve(),
+            log: file.log.resolve(),
+            offline_csv,
+            cassette: cli.cassette,
+        })
+    }
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        AppConfig {
+            api_url: parse_base_url(DEFAULT_API_URL).expect("default URL is valid"),
+            http: HttpConfig::default(),
+            auth: AuthConfig::default(),
+            retry: RetryPolicy::default(),
+            cache: CacheConfig::default(),
+            disk_cache: DiskCacheConfig::default(),
+            health: HealthConfig::default(),
+            breaker: BreakerConfig::default(),
+            log: LogConfig::default(),
+            offline_csv: None,
+            cassette: None,
+        }
+    }
+}
+
+fn read_config_file(path: &Path) -> Result<ConfigFile, Box<dyn Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read config file {}: {}", path.display(), e))?;
+    let file = toml::from_str(&contents)
+        .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?;
+    Ok(file)
+}
+
+/// Accepts `--flag value` and `--flag=value`. Unknown arguments are ignored so
+/// launcher-specific flags don't break startup.
+fn parse_args(mut args: impl Iterator<Item = String>) -> CliArgs {
+    let mut cli = CliArgs::default();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+        let mut value = || inline.clone().or_else(|| args.next());
+
+        match flag.as_str() {
+            "--api-url" => cli.api_url = value(),
+            "--offline-csv" => cli.offline_csv = value().map(PathBuf::from),
+            "--config" => cli.config = value().map(PathBuf::from),
+            "--record" | "--replay" => {
+                let mode = if flag == "--record" {
+                    CassetteMode::Record
+                } else {
+                    CassetteMode::Replay
+                };
+                cli.cassette = value().map(|path| CassetteConfig {
+                    mode,
+                    path: PathBuf::from(path),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    cli
+}
+
+/// Parses a base URL and makes sure its path ends with `/`, so joining
+/// relative segments never drops the last path component of the base.
+pub fn parse_base_url(raw: &str) -> Result<Url, Box<dyn Error>> {
+    let mut url =
+        Url::parse(raw.trim()).map_err(|e| format!("Invalid API URL '{}': {}", raw, e))?;
+    if url.cannot_be_a_base() || !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("API URL '{}' must be an http(s) URL", raw).into());
+    }
+    if !url.path().ends_with('/') {
+        let path = format!("{}/", url.path());
+        url.set_path(&path);
+    }
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn load(cli: &[&str], env: &[(&str, &str)]) -> Result<AppConfig, Box<dyn Error>> {
+        let env: HashMap<String, String> = env
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        AppConfig::load_from(args(cli), |name| env.get(name).cloned())
+    }
+
+    #[test]
+    fn flags_take_a_separate_or_inline_value() {
+        let cli = parse_args(args(&[
+            "--api-url=http://inline/?a=b",
+            "--unknown",
+            "--offline-csv",
+            "trips.csv",
+            "--replay=trips.json",
+        ]));
+        assert_eq!(cli.api_url.as_deref(), Some("http://inline/?a=b"));
+        assert_eq!(cli.offline_csv, Some(PathBuf::from("trips.csv")));
+        assert_eq!(cli.config, None);
+        assert_eq!(
+            cli.cassette,
+            Some(CassetteConfig {
+                mode: CassetteMode::Replay,
+                path: PathBuf::from("trips.json"),
+            })
+        );
+
+        // A flag at the end without its value is left unset
+        assert_eq!(parse_args(args(&["--config"])).config, None);
+    }
+
+    #[test]
+    fn flag_wins_over_env_over_file_over_default() {
+        let path = std::env::temp_dir().join(format!("config-test-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            "api_url = \"http://file/\"\noffline_csv = \"file.csv\"\n[auth]\napi_key = \"from-file\"\n",
+        )
+        .unwrap();
+        let config = path.to_str().unwrap();
+        let env = [
+            (CONFIG_PATH_ENV, config),
+            (API_URL_ENV, "http://env/"),
+            (OFFLINE_CSV_ENV, "env.csv"),
+            (API_KEY_ENV, "from-env"),
+        ];
+
+        let flagged = load(
+            &["--api-url", "http://flag/", "--offline-csv=flag.csv"],
+            &env,
+        )
+        .unwrap();
+        assert_eq!(flagged.api_url.as_str(), "http://flag/");
+        assert_eq!(flagged.offline_csv, Some(PathBuf::from("flag.csv")));
+
+        let from_env = load(&[], &env).unwrap();
+        assert_eq!(from_env.api_url.as_str(), "http://env/");
+        assert_eq!(from_env.offline_csv, Some(PathBuf::from("env.csv")));
+        assert_eq!(from_env.auth.api_key, Some(Secret::new("from-env")));
+
+        let from_file = load(&["--config", config], &[]).unwrap();
+        assert_eq!(from_file.api_url.as_str(), "http://file/");
+        assert_eq!(from_file.offline_csv, Some(PathBuf::from("file.csv")));
+        assert_eq!(from_file.auth.api_key, Some(Secret::new("from-file")));
+        let _ = std::fs::remove_file(&path);
+
+        let defaults = load(&[], &[]).unwrap();
+        assert_eq!(defaults.api_url.as_str(), DEFAULT_API_URL);
+        assert_eq!(defaults.offline_csv, None);
+
+        assert!(load(&["--config", "missing.toml"], &[]).is_err());
+    }
+
+    #[test]
+    fn base_url_keeps_its_path_when_joined() {
+        for raw in ["http://h/api", "http://h/api/", " http://h/api "] {
+            let url = parse_base_url(raw).unwrap();
+            assert_eq!(url.as_str(), "http://h/api/");
+            assert_eq!(url.join("trip/5").unwrap().as_str(), "http://h/api/trip/5");
+        }
+        assert_eq!(
+            parse_base_url("https://h")
+                .unwrap()
+                .join("trip/5")
+                .unwrap()
+                .as_str(),
+            "https://h/trip/5"
+        );
+
+        for raw in ["ftp://h/", "mailto:someone@h", "h/api", ""] {
+            assert!(parse_base_url(raw).is_err(), "{}", raw);
+        }
+    }
+}
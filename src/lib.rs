@@ -0,0 +1,5 @@
+//! API layer and configuration shared by the desktop app and the
+//! `mock-server` binary.
+
+pub mod api;
+pub mod config;
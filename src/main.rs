@@ -1,6 +1,46 @@
+mod components;
+
+use components::batch_panel::BatchPanel;
+use components::breaker_badge::{breaker_progress, BreakerBadge};
+use components::cache_age_badge::CacheAgeBadge;
+use components::disk_cache_panel::DiskCachePanel;
+use components::error_notice::{describe_error, ErrorNotice};
+use components::field_error::FieldErrorText;
+use components::filter_panel::FilterPanel;
+use components::health_badge::HealthBadge;
+use components::network_panel::NetworkPanel;
+use components::rejected_records::RejectedRecords;
+use components::trip_table::{format_money, TripColumn, TripTable};
+use components::util::{cancel_task, with_refresh};
 use dioxus::prelude::*;
 
-mod api;
+use frontend_local_rust_os::api::apicalls::TripApiClient;
+use frontend_local_rust_os::api::backend::SharedBackend;
+use frontend_local_rust_os::api::breaker::{CircuitBreaker, CircuitBreakerBackend, CircuitState};
+use frontend_local_rust_os::api::cache::CachedBackend;
+use frontend_local_rust_os::api::coalesce::CoalescingBackend;
+use frontend_local_rust_os::api::destinations::{get_by_destinations, DEFAULT_CONCURRENCY};
+use frontend_local_rust_os::api::disk_cache::{DiskCache, DiskCachedBackend};
+use frontend_local_rust_os::api::error::ApiError;
+use frontend_local_rust_os::api::health::{HealthMonitor, HealthState, HealthStatus};
+use frontend_local_rust_os::api::inspector::{NetworkLayer, NetworkLog};
+use frontend_local_rust_os::api::local::LocalBackend;
+use frontend_local_rust_os::api::page::{Page, PageRequest};
+use frontend_local_rust_os::api::query::{destination_query, price_range_query, PagingForm};
+use frontend_local_rust_os::api::retry::{observe_retries, RetryEvent};
+use frontend_local_rust_os::api::trip::Trip;
+use frontend_local_rust_os::config::{AppConfig, LogConfig, LogRotation};
+use futures::StreamExt;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::prelude::*;
+
+// Llamadas que conserva el inspector de red
+const NETWORK_LOG_CAPACITY: usize = 200;
 
 fn main() {
     // Configuraciones para evitar problemas gráficos
@@ -8,138 +48,415 @@ fn main() {
     std::env::set_var("LIBGL_ALWAYS_SOFTWARE", "1");
     std::env::set_var("WEBKIT_DISABLE_COMPOSITING_MODE", "1");
 
-    dioxus::launch(App);
+    // URL del backend: --api-url, TRIP_API_URL o archivo de configuración
+    let config = match AppConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error de configuración: {}", e);
+            std::process::exit(2);
+        }
+    };
+    let network_log = NetworkLog::new(NETWORK_LOG_CAPACITY);
+    init_tracing(&config.log, network_log.clone());
+    let disk_cache = open_disk_cache(&config);
+    let breaker = circuit_breaker(&config);
+    let backend = match build_backend(&config, disk_cache.clone(), breaker.clone()) {
+        Ok(backend) => backend,
+        Err(e) => {
+            eprintln!("Error al preparar la fuente de datos: {}", e);
+            std::process::exit(2);
+        }
+    };
+    tracing::info!(backend = %backend.describe(), "using backend");
+
+    dioxus::LaunchBuilder::new()
+        .with_context(config)
+        .with_context(backend)
+        .with_context(disk_cache)
+        .with_context(breaker)
+        .with_context(network_log)
+        .launch(App);
+}
+
+// Las llamadas al backend van al inspector de red y, si está activado, al
+// archivo de registro; sin archivo la app sigue funcionando
+fn init_tracing(config: &LogConfig, network_log: NetworkLog) {
+    let file = if config.enabled {
+        match open_log_file(config) {
+            Ok(appender) => Some(appender),
+            Err(e) => {
+                eprintln!("Registro en archivo desactivado: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let file_layer = file.map(|appender| {
+        tracing_subscriber::fmt::layer()
+            .with_writer(appender)
+            .with_ansi(false)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_filter(Targets::new().with_target(env!("CARGO_CRATE_NAME"), LevelFilter::INFO))
+    });
+    tracing_subscriber::registry()
+        .with(file_layer)
+        .with(NetworkLayer::new(network_log))
+        .init();
+}
+
+fn open_log_file(config: &LogConfig) -> Result<RollingFileAppender, Box<dyn Error>> {
+    let dir = config
+        .dir
+        .as_ref()
+        .ok_or("no hay directorio de datos para los registros")?;
+    let rotation = match config.rotation {
+        LogRotation::Hourly => Rotation::HOURLY,
+        LogRotation::Daily => Rotation::DAILY,
+        LogRotation::Never => Rotation::NEVER,
+    };
+    let appender = RollingFileAppender::builder()
+        .rotation(rotation)
+        .filename_prefix("requests")
+        .filename_suffix("log")
+        .max_log_files(config.max_files)
+        .build(dir)?;
+    Ok(appender)
+}
+
+// Modo sin conexión si hay un CSV configurado; si no, el backend HTTP con
+// cortocircuito, copia en disco, consultas idénticas simultáneas unificadas y
+// caché en memoria. La copia en disco queda por encima del cortocircuito para
+// responder mientras está abierto
+fn build_backend(
+    config: &AppConfig,
+    disk_cache: Option<Arc<DiskCache>>,
+    breaker: Option<CircuitBreaker>,
+) -> Result<SharedBackend, Box<dyn Error>> {
+    if let Some(path) = &config.offline_csv {
+        return Ok(Arc::new(LocalBackend::from_csv(path)?));
+    }
+
+    let mut backend: SharedBackend = Arc::new(TripApiClient::from_config(config)?);
+    if let Some(breaker) = breaker {
+        backend = Arc::new(CircuitBreakerBackend::new(backend, breaker));
+    }
+    if let Some(disk_cache) = disk_cache {
+        backend = Arc::new(DiskCachedBackend::new(backend, disk_cache));
+    }
+    backend = Arc::new(CoalescingBackend::new(backend));
+    if config.cache.enabled {
+        backend = Arc::new(CachedBackend::new(backend, &config.cache));
+    }
+    Ok(backend)
+}
+
+// Sin backend HTTP no hay nada que cortar
+fn circuit_breaker(config: &AppConfig) -> Option<CircuitBreaker> {
+    (config.breaker.enabled && config.offline_csv.is_none())
+        .then(|| CircuitBreaker::new(config.breaker.clone()))
+}
+
+// La copia en disco es opcional: si no se puede abrir, la app sigue sin ella.
+// Con una grabación activa no se usa, para que sus copias no oculten una
+// consulta sin grabar
+fn open_disk_cache(config: &AppConfig) -> Option<Arc<DiskCache>> {
+    let settings = &config.disk_cache;
+    if !settings.enabled || config.offline_csv.is_some() || config.cassette.is_some() {
+        return None;
+    }
+    let path = settings.path.as_ref()?;
+    match DiskCache::open(path, settings.max_bytes) {
+        Ok(cache) => Some(Arc::new(cache)),
+        Err(e) => {
+            eprintln!("Caché en disco desactivada: {}", e);
+            None
+        }
+    }
 }
 
 #[component]
 fn App() -> Element {
+    // Fuente de datos compartida (HTTP por defecto), recibida por contexto
+    let backend = use_signal(consume_context::<SharedBackend>);
+
+    // Estado del backend según comprobaciones periódicas; cualquier mensaje
+    // enviado a la corrutina adelanta la siguiente comprobación
+    let mut health = use_signal(HealthStatus::default);
+    let health_check = use_coroutine(move |mut wake: UnboundedReceiver<()>| {
+        let config = consume_context::<AppConfig>();
+        async move {
+            if !config.health.enabled || config.offline_csv.is_some() {
+                return;
+            }
+            let mut monitor = HealthMonitor::new(config.health.clone());
+            loop {
+                let backend = backend();
+                health.set(monitor.check(&*backend).await.clone());
+                tokio::select! {
+                    _ = tokio::time::sleep(monitor.next_delay()) => {}
+                    _ = wake.next() => {}
+                }
+            }
+        }
+    });
+
+    // Estado del cortocircuito; mientras está abierto se actualiza cada
+    // segundo para la cuenta atrás y para notar el final de la espera
+    let breaker_handle = use_signal(consume_context::<Option<CircuitBreaker>>);
+    let mut breaker = use_signal(|| breaker_handle.peek().as_ref().map(CircuitBreaker::status));
+    use_future(move || async move {
+        let Some(handle) = breaker_handle.peek().clone() else {
+            return;
+        };
+        let mut changes = handle.subscribe();
+        loop {
+            let status = handle.status();
+            let tick = status
+                .retry_in()
+                .map(|left| left.min(Duration::from_secs(1)));
+            breaker.set(Some(status));
+            let changed = match tick {
+                Some(tick) => tokio::select! {
+                    changed = changes.changed() => changed.is_ok(),
+                    _ = tokio::time::sleep(tick) => true,
+                },
+                None => changes.changed().await.is_ok(),
+            };
+            if !changed {
+                return;
+            }
+        }
+    });
+
     let mut count = use_signal(|| 0);
 
-    let mut trip_data = use_signal(|| None::<String>);
+    let mut trip_data = use_signal(|| None::<(String, Option<Duration>)>);
+    let mut trip_error = use_signal(|| None::<ApiError>);
     let mut loading = use_signal(|| false);
+    let mut trip_retry = use_signal(|| None::<RetryEvent>);
+    let mut trip_task = use_signal(|| None::<Task>);
+    let mut trip_paused = use_signal(|| false);
 
     let mut min_price = use_signal(|| "10.0".to_string());
     let mut max_price = use_signal(|| "20.0".to_string());
-    let mut price_page = use_signal(|| "1".to_string());
-    let mut price_per_page = use_signal(|| "5".to_string());
-    let mut price_results = use_signal(|| None::<api::apicalls::GetByPriceRangeOutput>);
+    let mut price_paging = use_signal(|| PagingForm::from_request(PageRequest::first(5)));
+    let mut price_results = use_signal(|| None::<Page<Trip>>);
     let mut price_loading = use_signal(|| false);
+    let mut price_retry = use_signal(|| None::<RetryEvent>);
+    let mut price_error = use_signal(|| None::<ApiError>);
+    let mut price_task = use_signal(|| None::<Task>);
+    let mut price_paused = use_signal(|| None::<bool>);
     let mut index_search = use_signal(|| "1".to_string());
 
     let mut destination = use_signal(|| "236".to_string());
-    let mut dest_page = use_signal(|| "1".to_string());
-    let mut dest_per_page = use_signal(|| "5".to_string());
-    let mut dest_results = use_signal(|| None::<api::apicalls::GetByPriceRangeOutput>);
+    let mut dest_paging = use_signal(|| PagingForm::from_request(PageRequest::first(5)));
+    let mut dest_results = use_signal(|| None::<Page<Trip>>);
     let mut dest_loading = use_signal(|| false);
-
-    let fetch_trip = move |_| {
+    let mut dest_retry = use_signal(|| None::<RetryEvent>);
+    let mut dest_error = use_signal(|| None::<ApiError>);
+    let mut dest_task = use_signal(|| None::<Task>);
+    let mut dest_paused = use_signal(|| None::<bool>);
+
+    // Cada consulta nueva cancela la anterior de su sección, así una respuesta
+    // lenta nunca pisa a una más reciente
+    let mut fetch_trip = move |_| {
+        cancel_task(trip_task);
         loading.set(true);
+        trip_error.set(None);
+        trip_retry.set(None);
+        // Con el backend caído la consulta espera a que vuelva
+        let paused = backend_down(health);
+        trip_paused.set(paused);
+        if paused {
+            return;
+        }
+        let backend = backend();
+        let index = index_search.peek().to_string();
 
-        spawn(async move {
-            match api::apicalls::get_by_index(index_search.peek().to_string()).await {
+        let task = spawn(async move {
+            match observe_retries(retry_status(trip_retry), backend.get_by_index(&index)).await {
                 Ok(trip) => {
-                    trip_data.set(Some(format!(
-                        "Viaje encontrado: Origen {} → Destino {}, Distancia: {}, Importe: ${} USD",
-                        trip.pu_location_id,
-                        trip.do_location_id,
-                        trip.trip_distance,
-                        trip.fare_amount,
-                    )));
+                    trip_data.set(Some((describe_trip(&trip), trip.cache_age)));
                     loading.set(false);
                 }
+                // Cortocircuito abierto y sin copia guardada: espera a que cierre
+                Err(ApiError::Unavailable { .. }) => trip_paused.set(true),
                 Err(e) => {
-                    trip_data.set(Some(format!("Error: {}", e)));
+                    trip_data.set(None);
+                    trip_error.set(Some(e));
                     loading.set(false);
                 }
             }
+            trip_task.set(None);
         });
+        trip_task.set(Some(task));
     };
 
-    let mut fetch_by_price = move |_| {
-        price_loading.set(true);
-
-        let input = api::apicalls::GetByPriceRangeInput {
-            min: min_price.peek().to_string(),
-            max: max_price.peek().to_string(),
-            page: price_page.peek().to_string(),
-            per_page: price_per_page.peek().to_string(),
+    let mut fetch_by_price = move |refresh: bool| {
+        // El botón ya está deshabilitado con el formulario inválido; esto cubre
+        // una consulta en pausa cuyo formulario se editó mientras tanto
+        let Ok(input) =
+            price_range_query(&min_price.peek(), &max_price.peek(), &price_paging.peek())
+        else {
+            price_loading.set(false);
+            price_paused.set(None);
+            return;
         };
-
-        spawn(async move {
-            match api::apicalls::get_by_price_range(&input).await {
+        cancel_task(price_task);
+        price_loading.set(true);
+        price_error.set(None);
+        price_retry.set(None);
+        let paused = backend_down(health);
+        price_paused.set(paused.then_some(refresh));
+        if paused {
+            return;
+        }
+        let backend = backend();
+
+        let task = spawn(async move {
+            match observe_retries(
+                retry_status(price_retry),
+                with_refresh(refresh, backend.get_by_price_range(&input)),
+            )
+            .await
+            {
                 Ok(result) => {
                     price_results.set(Some(result));
                     price_loading.set(false);
                 }
+                Err(ApiError::Unavailable { .. }) => price_paused.set(Some(refresh)),
                 Err(e) => {
                     price_results.set(None);
                     price_loading.set(false);
-                    trip_data.set(Some(format!("Error en búsqueda por precio: {}", e)));
+                    price_error.set(Some(e));
                 }
             }
+            price_task.set(None);
         });
+        price_task.set(Some(task));
     };
 
-    let mut fetch_by_destination = move |_| {
-        dest_loading.set(true);
-
-        let input = api::apicalls::GetByDestinationInput {
-            destination: destination.peek().to_string(),
-            page: dest_page.peek().to_string(),
-            per_page: dest_per_page.peek().to_string(),
+    let mut fetch_by_destination = move |refresh: bool| {
+        let Ok(input) = destination_query(&destination.peek(), &dest_paging.peek()) else {
+            dest_loading.set(false);
+            dest_paused.set(None);
+            return;
         };
-
-        spawn(async move {
-            match api::apicalls::get_by_destination(&input).await {
+        cancel_task(dest_task);
+        dest_loading.set(true);
+        dest_error.set(None);
+        dest_retry.set(None);
+        let paused = backend_down(health);
+        dest_paused.set(paused.then_some(refresh));
+        if paused {
+            return;
+        }
+        let backend = backend();
+
+        let task = spawn(async move {
+            match observe_retries(
+                retry_status(dest_retry),
+                with_refresh(
+                    refresh,
+                    get_by_destinations(backend.as_ref(), &input, DEFAULT_CONCURRENCY),
+                ),
+            )
+            .await
+            {
                 Ok(result) => {
                     dest_results.set(Some(result));
                     dest_loading.set(false);
                 }
+                Err(ApiError::Unavailable { .. }) => dest_paused.set(Some(refresh)),
                 Err(e) => {
                     dest_results.set(None);
                     dest_loading.set(false);
-                    trip_data.set(Some(format!("Error en búsqueda por destino: {}", e)));
+                    dest_error.set(Some(e));
                 }
             }
+            dest_task.set(None);
         });
+        dest_task.set(Some(task));
+    };
+
+    let mut cancel_trip = move |_| {
+        cancel_task(trip_task);
+        loading.set(false);
+        trip_retry.set(None);
+        trip_paused.set(false);
+    };
+
+    let mut cancel_price = move |_| {
+        cancel_task(price_task);
+        price_loading.set(false);
+        price_retry.set(None);
+        price_paused.set(None);
+    };
+
+    let mut cancel_dest = move |_| {
+        cancel_task(dest_task);
+        dest_loading.set(false);
+        dest_retry.set(None);
+        dest_paused.set(None);
     };
 
+    // Al volver el backend se lanzan las consultas que quedaron en pausa; las
+    // pausadas por el cortocircuito, en cuanto deja de estar abierto (la
+    // primera es la prueba)
+    use_effect(move || {
+        let circuit_open = breaker().is_some_and(|status| status.state == CircuitState::Open);
+        if health().state == HealthState::Down || circuit_open {
+            return;
+        }
+        if trip_paused.take() {
+            fetch_trip(());
+        }
+        if let Some(refresh) = price_paused.take() {
+            fetch_by_price(refresh);
+        }
+        if let Some(refresh) = dest_paused.take() {
+            fetch_by_destination(refresh);
+        }
+    });
+
+    // La navegación parte de la página mostrada
     let prev_price_page = move |_| {
-        let current = price_page().parse::<i32>().unwrap_or(1);
-        if current > 1 {
-            price_page.set((current - 1).to_string());
-            fetch_by_price(());
+        if let Some(request) = price_results().and_then(|r| r.prev_request()) {
+            price_paging.set(PagingForm::from_request(request));
+            fetch_by_price(false);
         }
     };
 
     let next_price_page = move |_| {
-        if let Some(result) = price_results() {
-            let current = price_page().parse::<i32>().unwrap_or(1);
-            if current < result.pages as i32 {
-                price_page.set((current + 1).to_string());
-                fetch_by_price(());
-            }
+        if let Some(request) = price_results().and_then(|r| r.next_request()) {
+            price_paging.set(PagingForm::from_request(request));
+            fetch_by_price(false);
         }
     };
 
     let prev_dest_page = move |_| {
-        let current = dest_page().parse::<i32>().unwrap_or(1);
-        if current > 1 {
-            dest_page.set((current - 1).to_string());
-            fetch_by_destination(());
+        if let Some(request) = dest_results().and_then(|r| r.prev_request()) {
+            dest_paging.set(PagingForm::from_request(request));
+            fetch_by_destination(false);
         }
     };
 
     let next_dest_page = move |_| {
-        if let Some(result) = dest_results() {
-            let current = dest_page().parse::<i32>().unwrap_or(1);
-            if current < result.pages as i32 {
-                dest_page.set((current + 1).to_string());
-                fetch_by_destination(());
-            }
+        if let Some(request) = dest_results().and_then(|r| r.next_request()) {
+            dest_paging.set(PagingForm::from_request(request));
+            fetch_by_destination(false);
         }
     };
 
+    // Errores de cada campo, mostrados bajo su input mientras se escribe
+    let price_query = price_range_query(&min_price(), &max_price(), &price_paging());
+    let price_valid = price_query.is_ok();
+    let price_errors = price_query.err().unwrap_or_default();
+    let dest_query = destination_query(&destination(), &dest_paging());
+    let dest_valid = dest_query.is_ok();
+    let dest_errors = dest_query.err().unwrap_or_default();
+
     rsx! {
         div {
             style: "padding: 20px; font-family: sans-serif; background-color: #0378A6; color: white;",
@@ -149,6 +466,56 @@ fn App() -> Element {
                 "Visor de Datos de Viajes"
             }
 
+            p {
+                style: "text-align: center; margin-top: -20px; margin-bottom: 20px; opacity: 0.85;",
+                "Fuente de datos: {backend.read().describe()}"
+                HealthBadge { status: health() }
+                if let Some(status) = breaker() {
+                    BreakerBadge { status }
+                }
+            }
+
+            if let (HealthState::Down, Some(error)) = (health().state, health().last_error) {
+                div {
+                    style: "margin-bottom: 20px; padding: 12px; border-radius: 8px; background-color: #f8d7da; color: #721c24; border-left: 4px solid #BF5F56;",
+                    p { style: "margin: 0 0 5px 0; font-weight: bold;", "El backend no responde: {describe_error(&error).0}" }
+                    p { style: "margin: 0;", "Las consultas quedan en pausa y se lanzarán solas cuando vuelva la conexión." }
+                    button {
+                        style: "margin-top: 10px; padding: 6px 14px; background-color: #BF5F56; color: white; border: none; border-radius: 4px; cursor: pointer;",
+                        onclick: move |_| health_check.send(()),
+                        "Comprobar ahora"
+                    }
+                }
+            }
+
+            // Con el backend caído ya se ve el aviso anterior
+            if let Some(status) = breaker().filter(|status| {
+                status.state != CircuitState::Closed && health().state != HealthState::Down
+            }) {
+                div {
+                    style: "margin-bottom: 20px; padding: 12px; border-radius: 8px; background-color: #f8d7da; color: #721c24; border-left: 4px solid #BF5F56;",
+                    p {
+                        style: "margin: 0 0 5px 0; font-weight: bold;",
+                        "Backend no disponible tras {status.consecutive_failures} fallos seguidos"
+                        if let Some(error) = &status.last_error {
+                            ": {describe_error(error).0}"
+                        }
+                    }
+                    p { style: "margin: 0;", "{breaker_progress(&status)} Las consultas se responden con la copia guardada cuando existe; las demás quedan en pausa y se lanzarán solas cuando el backend vuelva." }
+                    button {
+                        style: "margin-top: 10px; padding: 6px 14px; background-color: #BF5F56; color: white; border: none; border-radius: 4px; cursor: pointer;",
+                        disabled: status.probing,
+                        onclick: move |_| {
+                            if let Some(handle) = breaker_handle.peek().as_ref() {
+                                handle.probe_now();
+                            }
+                            health_check.send(());
+                        },
+                        "Probar ahora"
+                    }
+                }
+            }
+
             div {
                 style: "margin-top: 30px; padding: 20px; background-color: #739FD9; border-radius: 8px; box-shadow: 0 4px 8px rgba(0,0,0,0.2);",
 
@@ -176,20 +543,39 @@ fn App() -> Element {
                 button {
                     style: "padding: 10px 20px; background-color: #BF5F56; color: white; border: none; border-radius: 4px; cursor: pointer; font-weight: bold; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
                     disabled: loading(),
-                    onclick: fetch_trip,
-                    {if loading() { "Cargando..." } else { "Obtener viaje" }}
+                    onclick: move |_| fetch_trip(()),
+                    {if loading() { loading_label(trip_retry(), trip_paused()) } else { "Obtener viaje".to_string() }}
+                }
+
+                if loading() {
+                    button {
+                        style: "margin-left: 10px; padding: 10px 20px; background-color: #012606; color: white; border: none; border-radius: 4px; cursor: pointer; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
+                        onclick: move |_| cancel_trip(()),
+                        "Cancelar"
+                    }
                 }
 
-                {trip_data().map(|data| {
+                {trip_data().map(|(data, cache_age)| {
                     rsx! {
                         div {
                             style: "margin-top: 15px; padding: 10px; border-radius: 4px; background-color: #D9A0AF; color: #012606; border-left: 4px solid #BF5F56;",
                             "{data}"
+                            CacheAgeBadge { age: cache_age }
                         }
                     }
                 })}
+
+                {trip_error().map(|error| rsx! {
+                    ErrorNotice {
+                        context: "Búsqueda por índice",
+                        error,
+                        on_retry: move |_| fetch_trip(()),
+                    }
+                })}
             }
 
+            BatchPanel {}
+
             div {
                 style: "margin-top: 30px; padding: 20px; background-color: #D9A0AF; border-radius: 8px; box-shadow: 0 4px 8px rgba(0,0,0,0.2);",
 
@@ -211,6 +597,7 @@ fn App() -> Element {
                             value: min_price,
                             oninput: move |evt| min_price.set(evt.value().clone()),
                         }
+                        FieldErrorText { error: price_errors.min }
                     }
 
                     div {
@@ -223,6 +610,7 @@ fn App() -> Element {
                             value: max_price,
                             oninput: move |evt| max_price.set(evt.value().clone()),
                         }
+                        FieldErrorText { error: price_errors.max }
                     }
 
                     div {
@@ -232,9 +620,10 @@ fn App() -> Element {
                         }
                         input {
                             style: "width: 90%; padding: 8px; border: 1px solid #012606; border-radius: 4px; background-color: rgba(255,255,255,0.9);",
-                            value: price_page,
-                            oninput: move |evt| price_page.set(evt.value().clone()),
+                            value: "{price_paging().page}",
+                            oninput: move |evt| price_paging.with_mut(|paging| paging.page = evt.value()),
                         }
+                        FieldErrorText { error: price_errors.paging.page }
                     }
 
                     div {
@@ -244,9 +633,10 @@ fn App() -> Element {
                         }
                         input {
                             style: "width: 90%; padding: 8px; border: 1px solid #012606; border-radius: 4px; background-color: rgba(255,255,255,0.9);",
-                            value: price_per_page,
-                            oninput: move |evt| price_per_page.set(evt.value().clone()),
+                            value: "{price_paging().per_page}",
+                            oninput: move |evt| price_paging.with_mut(|paging| paging.per_page = evt.value()),
                         }
+                        FieldErrorText { error: price_errors.paging.per_page }
                     }
                 }
 
@@ -255,26 +645,50 @@ fn App() -> Element {
 
                     button {
                         style: "padding: 10px 20px; background-color: #0378A6; color: white; border: none; border-radius: 4px; cursor: pointer; flex: 1; font-weight: bold; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
-                        disabled: price_loading(),
-                        onclick: move |_| fetch_by_price(()),
-                        {if price_loading() { "Cargando..." } else { "Buscar por rango de precio" }}
+                        disabled: price_loading() || !price_valid,
+                        onclick: move |_| fetch_by_price(false),
+                        {if price_loading() { loading_label(price_retry(), price_paused().is_some()) } else { "Buscar por rango de precio".to_string() }}
+                    }
+
+                    if price_loading() {
+                        button {
+                            style: "padding: 10px; background-color: #BF5F56; color: white; border: none; border-radius: 4px; cursor: pointer; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
+                            onclick: move |_| cancel_price(()),
+                            "Cancelar"
+                        }
                     }
 
                     button {
                         style: "padding: 10px; background-color: #012606; color: white; border: none; border-radius: 4px; cursor: pointer; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
-                        disabled: price_loading() || price_page() == "1",
+                        disabled: !price_valid || !price_results().is_some_and(|r| r.has_prev()),
                         onclick: prev_price_page,
                         "<<"
                     }
 
                     button {
                         style: "padding: 10px; background-color: #012606; color: white; border: none; border-radius: 4px; cursor: pointer; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
-                        disabled: price_loading() || price_results().map_or(true, |r| r.page >= r.pages),
+                        disabled: !price_valid || !price_results().is_some_and(|r| r.has_next()),
                         onclick: next_price_page,
                         ">>"
                     }
+
+                    button {
+                        style: "padding: 10px; background-color: #012606; color: white; border: none; border-radius: 4px; cursor: pointer; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
+                        title: "Volver a consultar el backend sin usar la caché",
+                        disabled: price_loading() || !price_valid || price_results().is_none(),
+                        onclick: move |_| fetch_by_price(true),
+                        "Refrescar"
+                    }
                 }
 
+                {price_error().map(|error| rsx! {
+                    ErrorNotice {
+                        context: "Búsqueda por precio",
+                        error,
+                        on_retry: move |_| fetch_by_price(false),
+                    }
+                })}
+
                 {price_results().map(|result| {
                     rsx! {
                         div {
@@ -282,38 +696,15 @@ fn App() -> Element {
 
                             div {
                                 style: "padding: 10px; border-radius: 4px; background-color: #739FD9; color: white; margin-bottom: 10px;",
-                                p { "Total: {result.total} viajes | Página {result.page} de {result.pages} | Tiempo: {result.time_ms}ms" }
+                                p {
+                                    "Viajes {result.range_text()} | Página {result.page} de {result.pages} | Tiempo: {result.time_ms}ms"
+                                    CacheAgeBadge { age: result.cache_age }
+                                }
                             }
 
-                            table {
-                                style: "width: 100%; border-collapse: collapse; background-color: white; border-radius: 4px; overflow: hidden;",
-
-                                thead {
-                                    tr {
-                                        style: "background-color: #012606; color: white;",
-                                        th { style: "padding: 10px; text-align: left; border-bottom: 1px solid #739FD9;", "Índice" }
-                                        th { style: "padding: 10px; text-align: left; border-bottom: 1px solid #739FD9;", "Origen" }
-                                        th { style: "padding: 10px; text-align: left; border-bottom: 1px solid #739FD9;", "Destino" }
-                                        th { style: "padding: 10px; text-align: left; border-bottom: 1px solid #739FD9;", "Distancia" }
-                                        th { style: "padding: 10px; text-align: left; border-bottom: 1px solid #739FD9;", "Importe" }
-                                    }
-                                }
+                            RejectedRecords { rejected: result.rejected.clone() }
 
-                                tbody {
-                                    {result.items.iter().map(|trip| {
-                                        rsx! {
-                                            tr {
-                                                key: "{trip.index}",
-                                                td { style: "padding: 8px; text-align: left; border-bottom: 1px solid #D9A0AF; color: #012606;", "{trip.index}" }
-                                                td { style: "padding: 8px; text-align: left; border-bottom: 1px solid #D9A0AF; color: #012606;", "{trip.pu_location_id}" }
-                                                td { style: "padding: 8px; text-align: left; border-bottom: 1px solid #D9A0AF; color: #012606;", "{trip.do_location_id}" }
-                                                td { style: "padding: 8px; text-align: left; border-bottom: 1px solid #D9A0AF; color: #012606;", "{trip.trip_distance}" }
-                                                td { style: "padding: 8px; text-align: left; border-bottom: 1px solid #D9A0AF; color: #012606;", "${trip.total_amount}" }
-                                            }
-                                        }
-                                    })}
-                                }
-                            }
+                            TripTable { trips: result.items.clone(), columns: TripColumn::SUMMARY }
                         }
                     }
                 })}
@@ -333,13 +724,16 @@ fn App() -> Element {
                     div {
                         label {
                             style: "color: white; display: block; margin-bottom: 5px;",
-                            "ID de Destino:"
+                            "IDs de Destino:"
                         }
                         input {
                             style: "width: 90%; padding: 8px; border: 1px solid #012606; border-radius: 4px; background-color: rgba(255,255,255,0.9);",
+                            title: "Un ID, una lista o rangos de IDs",
+                            placeholder: "Ej: 236 o 1, 132, 138 o 100-120",
                             value: destination,
                             oninput: move |evt| destination.set(evt.value().clone()),
                         }
+                        FieldErrorText { error: dest_errors.destination }
                     }
 
                     div {
@@ -349,9 +743,10 @@ fn App() -> Element {
                         }
                         input {
                             style: "width: 90%; padding: 8px; border: 1px solid #012606; border-radius: 4px; background-color: rgba(255,255,255,0.9);",
-                            value: dest_page,
-                            oninput: move |evt| dest_page.set(evt.value().clone()),
+                            value: "{dest_paging().page}",
+                            oninput: move |evt| dest_paging.with_mut(|paging| paging.page = evt.value()),
                         }
+                        FieldErrorText { error: dest_errors.paging.page }
                     }
 
                     div {
@@ -361,9 +756,10 @@ fn App() -> Element {
                         }
                         input {
                             style: "width: 90%; padding: 8px; border: 1px solid #012606; border-radius: 4px; background-color: rgba(255,255,255,0.9);",
-                            value: dest_per_page,
-                            oninput: move |evt| dest_per_page.set(evt.value().clone()),
+                            value: "{dest_paging().per_page}",
+                            oninput: move |evt| dest_paging.with_mut(|paging| paging.per_page = evt.value()),
                         }
+                        FieldErrorText { error: dest_errors.paging.per_page }
                     }
                 }
 
@@ -372,26 +768,50 @@ fn App() -> Element {
 
                     button {
                         style: "padding: 10px 20px; background-color: #BF5F56; color: white; border: none; border-radius: 4px; cursor: pointer; flex: 1; font-weight: bold; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
-                        disabled: dest_loading(),
-                        onclick: move |_| fetch_by_destination(()),
-                        {if dest_loading() { "Cargando..." } else { "Buscar por destino" }}
+                        disabled: dest_loading() || !dest_valid,
+                        onclick: move |_| fetch_by_destination(false),
+                        {if dest_loading() { loading_label(dest_retry(), dest_paused().is_some()) } else { "Buscar por destino".to_string() }}
+                    }
+
+                    if dest_loading() {
+                        button {
+                            style: "padding: 10px; background-color: #0378A6; color: white; border: none; border-radius: 4px; cursor: pointer; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
+                            onclick: move |_| cancel_dest(()),
+                            "Cancelar"
+                        }
                     }
 
                     button {
                         style: "padding: 10px; background-color: #012606; color: white; border: none; border-radius: 4px; cursor: pointer; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
-                        disabled: dest_loading() || dest_page() == "1",
+                        disabled: !dest_valid || !dest_results().is_some_and(|r| r.has_prev()),
                         onclick: prev_dest_page,
                         "<<"
                     }
 
                     button {
                         style: "padding: 10px; background-color: #012606; color: white; border: none; border-radius: 4px; cursor: pointer; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
-                        disabled: dest_loading() || dest_results().map_or(true, |r| r.page >= r.pages),
+                        disabled: !dest_valid || !dest_results().is_some_and(|r| r.has_next()),
                         onclick: next_dest_page,
                         ">>"
                     }
+
+                    button {
+                        style: "padding: 10px; background-color: #012606; color: white; border: none; border-radius: 4px; cursor: pointer; box-shadow: 0 2px 4px rgba(0,0,0,0.1);",
+                        title: "Volver a consultar el backend sin usar la caché",
+                        disabled: dest_loading() || !dest_valid || dest_results().is_none(),
+                        onclick: move |_| fetch_by_destination(true),
+                        "Refrescar"
+                    }
                 }
 
+                {dest_error().map(|error| rsx! {
+                    ErrorNotice {
+                        context: "Búsqueda por destino",
+                        error,
+                        on_retry: move |_| fetch_by_destination(false),
+                    }
+                })}
+
                 {dest_results().map(|result| {
                     rsx! {
                         div {
@@ -399,42 +819,82 @@ fn App() -> Element {
 
                             div {
                                 style: "padding: 10px; border-radius: 4px; background-color: #D9A0AF; color: #012606; margin-bottom: 10px;",
-                                p { "Total: {result.total} viajes | Página {result.page} de {result.pages} | Tiempo: {result.time_ms}ms" }
+                                p {
+                                    "Viajes {result.range_text()} | Página {result.page} de {result.pages} | Tiempo: {result.time_ms}ms"
+                                    CacheAgeBadge { age: result.cache_age }
+                                }
                             }
 
-                            table {
-                                style: "width: 100%; border-collapse: collapse; background-color: white; border-radius: 4px; overflow: hidden;",
-
-                                thead {
-                                    tr {
-                                        style: "background-color: #012606; color: white;",
-                                        th { style: "padding: 10px; text-align: left; border-bottom: 1px solid #739FD9;", "Índice" }
-                                        th { style: "padding: 10px; text-align: left; border-bottom: 1px solid #739FD9;", "Origen" }
-                                        th { style: "padding: 10px; text-align: left; border-bottom: 1px solid #739FD9;", "Destino" }
-                                        th { style: "padding: 10px; text-align: left; border-bottom: 1px solid #739FD9;", "Distancia" }
-                                        th { style: "padding: 10px; text-align: left; border-bottom: 1px solid #739FD9;", "Importe" }
-                                    }
-                                }
+                            RejectedRecords { rejected: result.rejected.clone() }
 
-                                tbody {
-                                    {result.items.iter().map(|trip| {
-                                        rsx! {
-                                            tr {
-                                                key: "{trip.index}",
-                                                td { style: "padding: 8px; text-align: left; border-bottom: 1px solid #D9A0AF; color: #012606;", "{trip.index}" }
-                                                td { style: "padding: 8px; text-align: left; border-bottom: 1px solid #D9A0AF; color: #012606;", "{trip.pu_location_id}" }
-                                                td { style: "padding: 8px; text-align: left; border-bottom: 1px solid #D9A0AF; color: #012606;", "{trip.do_location_id}" }
-                                                td { style: "padding: 8px; text-align: left; border-bottom: 1px solid #D9A0AF; color: #012606;", "{trip.trip_distance}" }
-                                                td { style: "padding: 8px; text-align: left; border-bottom: 1px solid #D9A0AF; color: #012606;", "${trip.total_amount}" }
-                                            }
-                                        }
-                                    })}
-                                }
-                            }
+                            TripTable { trips: result.items.clone(), columns: TripColumn::SUMMARY }
                         }
                     }
                 })}
             }
+
+            FilterPanel {}
+
+            DiskCachePanel {}
+
+            div {
+                style: "margin-top: 30px; padding: 20px; background-color: #F2F2F2; border-radius: 8px; box-shadow: 0 4px 8px rgba(0,0,0,0.2); margin-bottom: 30px; color: #012606;",
+                NetworkPanel {}
+            }
         }
     }
 }
+
+// Resumen de un viaje usando los valores tipados cuando se pudieron interpretar
+fn describe_trip(trip: &Trip) -> String {
+    let pickup = trip
+        .tpep_pickup_datetime
+        .value()
+        .map(|dt| dt.format("%d/%m/%Y %H:%M").to_string())
+        .unwrap_or_else(|| trip.tpep_pickup_datetime.to_string());
+    let duration = trip
+        .duration()
+        .map(|d| format!("{} min", d.num_minutes()))
+        .unwrap_or_else(|| "?".to_string());
+    let label = |label: Option<&'static str>| label.unwrap_or("Desconocido");
+
+    format!(
+        "Viaje encontrado: Origen {} → Destino {}, Recogida: {}, Duración: {}, Distancia: {} mi, Pasajeros: {}, Pago: {}, Tarifa: {}, Proveedor: {}, Importe: ${} USD",
+        trip.pu_location_id,
+        trip.do_location_id,
+        pickup,
+        duration,
+        trip.trip_distance,
+        trip.passenger_count,
+        label(trip.payment_type.get().map(|p| p.label())),
+        label(trip.ratecode_id.get().map(|r| r.label())),
+        label(trip.vendor_id.get().map(|v| v.label())),
+        format_money(&trip.fare_amount),
+    )
+}
+
+fn backend_down(health: Signal<HealthStatus>) -> bool {
+    health.peek().state == HealthState::Down
+}
+
+// Observador que publica el último reintento en la señal de la sección
+fn retry_status(signal: Signal<Option<RetryEvent>>) -> impl Fn(&RetryEvent) {
+    move |event| {
+        let mut signal = signal;
+        signal.set(Some(event.clone()));
+    }
+}
+
+// Texto del botón mientras carga, con el reintento en curso si lo hay
+fn loading_label(retry: Option<RetryEvent>, paused: bool) -> String {
+    if paused {
+        return "En espera del backend...".to_string();
+    }
+    match retry {
+        Some(event) => format!(
+            "Cargando... (reintento {}/{})",
+            event.retry, event.max_retries
+        ),
+        None => "Cargando...".to_string(),
+    }
+}
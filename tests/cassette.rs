@@ -0,0 +1,141 @@
+//! Replays `fixtures/cassettes/trips.json`, recorded from the mock server
+//! with `fixtures/trips.csv`, through the real client.
+
+use frontend_local_rust_os::api::apicalls::{
+    GetByDestinationInput, GetByPriceRangeInput, TripApiClient,
+};
+use frontend_local_rust_os::api::cassette::Cassette;
+use frontend_local_rust_os::api::error::ApiError;
+use frontend_local_rust_os::api::page::PageRequest;
+use frontend_local_rust_os::config::AppConfig;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+
+const CASSETTE: &str = "fixtures/cassettes/trips.json";
+
+fn replay() -> (TripApiClient, Arc<Cassette>) {
+    let cassette = Arc::new(Cassette::replay(CASSETTE).unwrap());
+    let client = TripApiClient::from_config(&AppConfig::default())
+        .unwrap()
+        .with_cassette(cassette.clone());
+    (client, cassette)
+}
+
+fn price_range(min: &str, max: &str, per_page: u32) -> GetByPriceRangeInput {
+    GetByPriceRangeInput {
+        min: min.to_string(),
+        max: max.to_string(),
+        paging: PageRequest::new(1, per_page),
+    }
+}
+
+#[tokio::test]
+async fn replays_get_by_index() {
+    let (client, _) = replay();
+    let trip = client.get_by_index("5").await.unwrap();
+    assert_eq!(trip.index.raw(), "5");
+    assert_eq!(trip.total_amount.raw(), "11.20");
+    assert_eq!(trip.do_location_id.value(), Some(&249));
+
+    let missing = client.get_by_index("999999").await;
+    assert!(
+        matches!(missing, Err(ApiError::NotFound(_))),
+        "{:?}",
+        missing
+    );
+}
+
+#[tokio::test]
+async fn replays_get_by_price_range() {
+    let (client, _) = replay();
+    let page = client
+        .get_by_price_range(&price_range("10", "20", 3))
+        .await
+        .unwrap();
+    assert_eq!(page.total, 78);
+    assert_eq!(page.items.len(), 3);
+    assert!(page.rejected.is_empty());
+    for trip in &page.items {
+        let total = *trip.total_amount.value().unwrap();
+        assert!(
+            (Decimal::from(10)..=Decimal::from(20)).contains(&total),
+            "total {} out of range",
+            total
+        );
+    }
+}
+
+#[tokio::test]
+async fn replays_get_by_destination() {
+    let (client, _) = replay();
+    let page = client
+        .get_by_destination(&GetByDestinationInput {
+            destination: "142".to_string(),
+            paging: PageRequest::new(1, 3),
+        })
+        .await
+        .unwrap();
+    assert_eq!(page.total, 13);
+    assert_eq!(page.items.len(), 3);
+    assert!(page
+        .items
+        .iter()
+        .all(|trip| trip.do_location_id.value() == Some(&142)));
+}
+
+#[tokio::test]
+async fn every_recording_is_replayed() {
+    let (client, cassette) = replay();
+    client.is_alive().await.unwrap();
+    client.get_by_index("5").await.unwrap();
+    client.get_by_index("999999").await.unwrap_err();
+    client
+        .get_by_price_range(&price_range("10", "20", 3))
+        .await
+        .unwrap();
+    client
+        .get_by_destination(&GetByDestinationInput {
+            destination: "142".to_string(),
+            paging: PageRequest::new(1, 3),
+        })
+        .await
+        .unwrap();
+    assert_eq!(cassette.unplayed(), Vec::new());
+}
+
+#[tokio::test]
+async fn unrecorded_query_reports_the_differing_parameters() {
+    let (client, _) = replay();
+    let error = client
+        .get_by_price_range(&price_range("10", "25", 5))
+        .await
+        .unwrap_err();
+    let ApiError::Cassette(message) = error else {
+        panic!("expected a cassette error, got {:?}", error);
+    };
+    assert_eq!(
+        message,
+        format!(
+            "no recorded response in {} for GET /trip/price?max=25&min=10&page=1&per_page=5\n\
+             closest recording differs in the query parameters:\n  \
+             max: recorded \"20\", requested \"25\"\n  \
+             per_page: recorded \"3\", requested \"5\"",
+            CASSETTE
+        )
+    );
+}
+
+#[tokio::test]
+async fn unrecorded_path_lists_the_recorded_ones() {
+    let (client, _) = replay();
+    let error = client.get_by_index("6").await.unwrap_err();
+    let ApiError::Cassette(message) = error else {
+        panic!("expected a cassette error, got {:?}", error);
+    };
+    assert!(message.starts_with(&format!(
+        "no recorded response in {} for GET /trip/6\nnothing was recorded for that path; recorded paths:",
+        CASSETTE
+    )));
+    assert!(message.contains("\n  GET /trip/5"));
+    assert!(message.contains("\n  GET /trip/price"));
+}
@@ -0,0 +1,218 @@
+//! Runs the client against the `mock-server` binary serving
+//! `fixtures/trips.csv`, over plain HTTP or with the self-signed certificate
+//! in `fixtures/tls`.
+
+use frontend_local_rust_os::api::apicalls::{
+    GetByDestinationInput, GetByPriceRangeInput, TripApiClient,
+};
+use frontend_local_rust_os::api::error::ApiError;
+use frontend_local_rust_os::api::page::PageRequest;
+use frontend_local_rust_os::api::retry::RetryPolicy;
+use frontend_local_rust_os::config::{parse_base_url, HttpConfig};
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+
+/// The mock on a free port, stopped when dropped.
+struct MockServer {
+    child: Child,
+    url: String,
+}
+
+impl MockServer {
+    fn start(args: &[&str]) -> Self {
+        let data = fixture("trips.csv");
+        let mut child = Command::new(env!("CARGO_BIN_EXE_mock-server"))
+            .args(["--port", "0", "--data", data.to_str().unwrap()])
+            .args(args)
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("mock-server starts");
+
+        // Wait for the line with the address it bound
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let url = stdout
+            .lines()
+            .map_while(Result::ok)
+            .find_map(|line| {
+                line.split_once("listening on ")
+                    .map(|(_, url)| url.to_string())
+            })
+            .expect("mock-server prints its address");
+        MockServer { child, url }
+    }
+
+    /// The mock over HTTPS, with `fixtures/tls/localhost.crt`.
+    fn start_tls() -> Self {
+        let (cert, key) = (fixture("tls/localhost.crt"), fixture("tls/localhost.key"));
+        Self::start(&[
+            "--tls-cert",
+            cert.to_str().unwrap(),
+            "--tls-key",
+            key.to_str().unwrap(),
+        ])
+    }
+
+    fn client(&self, retry: RetryPolicy) -> TripApiClient {
+        client(&self.url, &HttpConfig::default(), retry)
+    }
+}
+
+fn client(url: &str, http: &HttpConfig, retry: RetryPolicy) -> TripApiClient {
+    TripApiClient::new(parse_base_url(url).unwrap(), http, retry).unwrap()
+}
+
+fn fixture(path: &str) -> PathBuf {
+    PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures")).join(path)
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn no_retries() -> RetryPolicy {
+    RetryPolicy {
+        max_attempts: 1,
+        ..RetryPolicy::default()
+    }
+}
+
+#[tokio::test]
+async fn serves_the_three_queries() {
+    let mock = MockServer::start(&[]);
+    let client = mock.client(no_retries());
+
+    assert!(client.is_alive().await.unwrap());
+
+    let trip = client.get_by_index("5").await.unwrap();
+    assert_eq!(trip.index.raw(), "5");
+    assert_eq!(trip.total_amount.raw(), "11.20");
+    let missing = client.get_by_index("999999").await;
+    assert!(
+        matches!(missing, Err(ApiError::NotFound(_))),
+        "{:?}",
+        missing
+    );
+
+    let page = client
+        .get_by_price_range(&GetByPriceRangeInput {
+            min: "10".to_string(),
+            max: "20".to_string(),
+            paging: PageRequest::new(2, 5),
+        })
+        .await
+        .unwrap();
+    assert_eq!((page.page, page.per_page, page.total), (2, 5, 78));
+    assert_eq!(page.items.len(), 5);
+
+    let page = client
+        .get_by_destination(&GetByDestinationInput {
+            destination: "142".to_string(),
+            paging: PageRequest::new(3, 5),
+        })
+        .await
+        .unwrap();
+    assert_eq!((page.page, page.pages, page.total), (3, 3, 13));
+    assert_eq!(page.items.len(), 3);
+    assert!(page
+        .items
+        .iter()
+        .all(|trip| trip.do_location_id.value() == Some(&142)));
+}
+
+#[tokio::test]
+async fn retries_through_a_cold_start() {
+    let mock = MockServer::start(&["--cold-start", "2"]);
+
+    let error = mock.client(no_retries()).get_by_index("5").await;
+    assert!(
+        matches!(error, Err(ApiError::Http { status: 503, .. })),
+        "{:?}",
+        error
+    );
+
+    let retry = RetryPolicy {
+        max_attempts: 3,
+        initial_backoff: std::time::Duration::from_millis(10),
+        ..RetryPolicy::default()
+    };
+    let trip = mock.client(retry).get_by_index("5").await.unwrap();
+    assert_eq!(trip.index.raw(), "5");
+}
+
+#[tokio::test]
+async fn rejects_requests_without_the_token() {
+    let mock = MockServer::start(&["--token", "secret"]);
+    let error = mock.client(no_retries()).get_by_index("5").await;
+    assert!(
+        matches!(error, Err(ApiError::Unauthorized(_))),
+        "{:?}",
+        error
+    );
+}
+
+#[tokio::test]
+async fn trusts_the_configured_ca_certificate() {
+    let mock = MockServer::start_tls();
+    assert!(mock.url.starts_with("https://127.0.0.1:"), "{}", mock.url);
+
+    let untrusted = client(&mock.url, &HttpConfig::default(), no_retries())
+        .get_by_index("5")
+        .await;
+    assert!(
+        matches!(untrusted, Err(ApiError::Transport(_))),
+        "{:?}",
+        untrusted
+    );
+
+    let http = HttpConfig {
+        ca_certs: vec![fixture("tls/localhost.crt")],
+        ..HttpConfig::default()
+    };
+    let trip = client(&mock.url, &http, no_retries())
+        .get_by_index("5")
+        .await
+        .unwrap();
+    assert_eq!(trip.index.raw(), "5");
+}
+
+#[tokio::test]
+async fn proxy_and_no_proxy_route_requests() {
+    let mock = MockServer::start(&[]);
+
+    // A host that never resolves, reached through the mock acting as proxy
+    let through_mock = HttpConfig {
+        proxy: Some(mock.url.parse().unwrap()),
+        ..HttpConfig::default()
+    };
+    let trip = client("http://trips.invalid/", &through_mock, no_retries())
+        .get_by_index("5")
+        .await
+        .unwrap();
+    assert_eq!(trip.index.raw(), "5");
+
+    // Listed in no_proxy, the same host is looked up directly and fails
+    let bypassed = HttpConfig {
+        no_proxy: vec!["trips.invalid".to_string()],
+        ..through_mock
+    };
+    let error = client("http://trips.invalid/", &bypassed, no_retries())
+        .get_by_index("5")
+        .await;
+    assert!(matches!(error, Err(ApiError::Transport(_))), "{:?}", error);
+
+    // A dead proxy doesn't matter for hosts in no_proxy
+    let dead_proxy = HttpConfig {
+        proxy: Some("http://127.0.0.1:9/".parse().unwrap()),
+        no_proxy: vec!["127.0.0.1".to_string()],
+        ..HttpConfig::default()
+    };
+    let trip = client(&mock.url, &dead_proxy, no_retries())
+        .get_by_index("5")
+        .await
+        .unwrap();
+    assert_eq!(trip.index.raw(), "5");
+}